@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub(crate) struct CacheSettings {
+    /// How many entries to keep before evicting the least recently used one. 0 disables the cache.
+    pub(crate) capacity: usize,
+    #[serde(with = "humantime_serde")]
+    pub(crate) ttl: Duration,
+    /// How long to remember that something does not exist
+    #[serde(with = "humantime_serde")]
+    pub(crate) negative_ttl: Duration,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            capacity: 1000,
+            ttl: Duration::from_secs(24 * 60 * 60),
+            negative_ttl: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// What the cache knows about a key: either its value, or that there is none.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Cached<V> {
+    Found(V),
+    Missing,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CacheStats {
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+    pub(crate) entries: usize,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: Cached<V>,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct Entries<V> {
+    map: HashMap<String, Entry<V>>,
+    /// Incremented on every access to know which entry was used least recently
+    clock: u64,
+}
+
+/// A bounded cache where entries expire after a while.
+/// Evicting scans all entries, which is fine for the few thousand Pokemon there are.
+#[derive(Debug)]
+pub(crate) struct Cache<V> {
+    settings: CacheSettings,
+    entries: Mutex<Entries<V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<V: Clone> Cache<V> {
+    pub(crate) fn new(settings: CacheSettings) -> Self {
+        Cache {
+            settings,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                clock: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Cached<V>> {
+        self.get_at(key, Instant::now())
+    }
+
+    pub(crate) fn insert(&self, key: &str, value: V) {
+        self.insert_at(key, Cached::Found(value), Instant::now())
+    }
+
+    pub(crate) fn insert_missing(&self, key: &str) {
+        self.insert_at(key, Cached::Missing, Instant::now())
+    }
+
+    pub(crate) fn settings(&self) -> &CacheSettings {
+        &self.settings
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.lock().map.len(),
+        }
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<Cached<V>> {
+        let mut entries = self.lock();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let found = match entries.map.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = clock;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.map.remove(key);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        found
+    }
+
+    fn insert_at(&self, key: &str, value: Cached<V>, now: Instant) {
+        if self.settings.capacity == 0 {
+            return;
+        }
+
+        let ttl = match value {
+            Cached::Found(_) => self.settings.ttl,
+            Cached::Missing => self.settings.negative_ttl,
+        };
+
+        let mut entries = self.lock();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        if !entries.map.contains_key(key) && entries.map.len() >= self.settings.capacity {
+            let least_recently_used = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+
+            if let Some(evicted) = least_recently_used {
+                entries.map.remove(&evicted);
+            }
+        }
+
+        entries.map.insert(
+            key.to_string(),
+            Entry {
+                value,
+                expires_at: now + ttl,
+                last_used: clock,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries<V>> {
+        self.entries
+            .lock()
+            .expect("lock for the cache was poisoned")
+    }
+}
+
+/// A value that is only loaded once it is first needed, such as an index of all species.
+/// After a failed load it isn't tried again for a while, so a broken upstream doesn't get
+/// the expensive request on every call.
+#[derive(Debug)]
+pub(crate) struct Lazy<T> {
+    value: OnceCell<T>,
+    failed_at: Mutex<Option<Instant>>,
+    retry_after: Duration,
+}
+
+impl<T> Lazy<T> {
+    pub(crate) fn new(retry_after: Duration) -> Self {
+        Lazy {
+            value: OnceCell::new(),
+            failed_at: Mutex::new(None),
+            retry_after,
+        }
+    }
+
+    /// The value, loading it first if needed. `None` if it couldn't be loaded, which is logged.
+    pub(crate) async fn get<F, Fut, E>(&self, what: &str, load: F) -> Option<&T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Display,
+    {
+        if let Some(value) = self.value.get() {
+            return Some(value);
+        }
+        if self
+            .lock()
+            .is_some_and(|failed_at| failed_at.elapsed() < self.retry_after)
+        {
+            return None;
+        }
+
+        match self.value.get_or_try_init(load).await {
+            Ok(value) => Some(value),
+            Err(error) => {
+                log::warn!("Unable to load {}: {}", what, error);
+                *self.lock() = Some(Instant::now());
+                None
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Instant>> {
+        self.failed_at
+            .lock()
+            .expect("lock for the lazy value was poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn cache(capacity: usize) -> Cache<&'static str> {
+        Cache::new(CacheSettings {
+            capacity,
+            ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn remembers_values_and_counts_hits_and_misses() {
+        let cache = cache(10);
+
+        assert_eq!(cache.get("mewtwo"), None);
+        cache.insert("mewtwo", "legendary");
+        assert_eq!(cache.get("mewtwo"), Some(Cached::Found("legendary")));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn entries_expire_after_their_ttl() {
+        let cache = cache(10);
+        let now = Instant::now();
+
+        cache.insert_at("mewtwo", Cached::Found("legendary"), now);
+        cache.insert_at("mewthree", Cached::Missing, now);
+
+        let later = now + Duration::from_secs(30);
+        assert_eq!(
+            cache.get_at("mewtwo", later),
+            Some(Cached::Found("legendary"))
+        );
+        // Things that don't exist are only remembered for a shorter while
+        assert_eq!(cache.get_at("mewthree", later), None);
+
+        let much_later = now + Duration::from_secs(61);
+        assert_eq!(cache.get_at("mewtwo", much_later), None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry_when_full() {
+        let cache = cache(2);
+
+        cache.insert("bulbasaur", "grass");
+        cache.insert("diglett", "ground");
+        cache.get("bulbasaur");
+        cache.insert("mewtwo", "psychic");
+
+        assert_eq!(cache.get("diglett"), None);
+        assert_eq!(cache.get("bulbasaur"), Some(Cached::Found("grass")));
+        assert_eq!(cache.get("mewtwo"), Some(Cached::Found("psychic")));
+    }
+
+    #[test]
+    fn a_capacity_of_zero_disables_the_cache() {
+        let cache = cache(0);
+
+        cache.insert("mewtwo", "legendary");
+
+        assert_eq!(cache.get("mewtwo"), None);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_a_failed_load_right_away() {
+        let lazy = Lazy::new(Duration::from_secs(60));
+
+        assert_eq!(lazy.get("the index", || async { Err("down") }).await, None);
+        assert_eq!(
+            lazy.get("the index", || async { Ok::<_, &str>(1) }).await,
+            None
+        );
+
+        *lazy.lock() = Some(Instant::now() - Duration::from_secs(61));
+        assert_eq!(
+            lazy.get("the index", || async { Ok::<_, &str>(1) }).await,
+            Some(&1)
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! The commands of the `oak` binary. Besides running the server, they look things up in PokeAPI
+//! and translate text right from the terminal, with the same clients the server uses.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::config::Args;
+use crate::local;
+use crate::pokeapi::{Error, Preferences};
+use crate::server::Clients;
+use crate::translation::Language;
+
+/// Pokemon and their descriptions, translated to Yoda speak and more
+#[derive(Debug, Parser)]
+#[command(name = "oak")]
+pub(crate) struct Cli {
+    #[command(flatten)]
+    pub(crate) args: Args,
+    /// How to print what the commands other than `serve` found
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub(crate) format: Format,
+    /// Runs the server without a command
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+pub(crate) enum Command {
+    /// Runs the server
+    Serve,
+    /// Works with the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    #[command(flatten)]
+    Query(Query),
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+pub(crate) enum ConfigCommand {
+    /// Validates the configuration without starting the server
+    Check,
+}
+
+/// The commands that look something up and print it
+#[derive(Debug, PartialEq, Subcommand)]
+pub(crate) enum Query {
+    /// Looks up a Pokemon by its name or national Pokédex number
+    Lookup {
+        name: String,
+        /// Translates the description like `/pokemon/translated/<name>` does
+        #[arg(long)]
+        translated: bool,
+    },
+    /// Translates text to a style such as yoda, pirate or german
+    Translate {
+        style: Language,
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+    /// Lists the species living in a habitat such as cave
+    Habitat { name: String },
+    /// Lists the legendary species
+    Legendaries,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub(crate) enum Format {
+    /// Readable columns, or one name per line
+    #[default]
+    Table,
+    /// For other tools, such as jq
+    Json,
+}
+
+#[derive(Serialize)]
+struct TranslatedText {
+    text: String,
+    style: Language,
+    original: String,
+}
+
+/// Runs the query, returning what to print or what went wrong.
+pub(crate) async fn run(query: Query, format: Format, clients: &Clients) -> Result<String, String> {
+    match query {
+        Query::Lookup { name, translated } => lookup(clients, &name, translated, format).await,
+        Query::Translate { style, text } => {
+            let original = text.join(" ");
+            let translated = clients
+                .translation
+                .translate(&original, style)
+                .await
+                .map_err(|error| error.to_string())?;
+
+            match format {
+                Format::Table => Ok(translated.text),
+                Format::Json => json(&TranslatedText {
+                    text: translated.text,
+                    style,
+                    original,
+                }),
+            }
+        }
+        Query::Habitat { name } => {
+            let species = clients
+                .poke_api
+                .habitat(&name)
+                .await
+                .map_err(|error| error.to_string())?;
+            list(species, format)
+        }
+        Query::Legendaries => {
+            let species = clients
+                .poke_api
+                .legendaries()
+                .await
+                .map_err(|error| error.to_string())?;
+            list(species, format)
+        }
+    }
+}
+
+async fn lookup(
+    clients: &Clients,
+    name: &str,
+    translated: bool,
+    format: Format,
+) -> Result<String, String> {
+    // The translation APIs only understand English
+    let preferences = Preferences {
+        languages: if translated {
+            vec!["en".into()]
+        } else {
+            Vec::new()
+        },
+        ..Preferences::default()
+    };
+
+    let mut pokemon = match clients.poke_api.find(name, &preferences).await {
+        Ok(pokemon) => pokemon,
+        Err(Error::NoSuchPokemon) => {
+            let suggestions = clients.poke_api.suggestions(name).await;
+            return Err(match suggestions.is_empty() {
+                true => format!("Unable to find '{}'", name),
+                false => format!(
+                    "Unable to find '{}', did you mean {}?",
+                    name,
+                    suggestions.join(", ")
+                ),
+            });
+        }
+        Err(error) => return Err(error.to_string()),
+    };
+
+    let style = translated.then(|| clients.rules.pick(&pokemon).style);
+    if let Some(style) = style {
+        pokemon.description = match clients
+            .translation
+            .translate(&pokemon.description, style)
+            .await
+        {
+            Ok(translated) => translated.text,
+            Err(error) => {
+                eprintln!("Falling back to a local translation: {}", error);
+                local::translate(&pokemon.description, style).unwrap_or(pokemon.description)
+            }
+        };
+    }
+
+    match format {
+        Format::Table => {
+            let mut rows = vec![
+                ("name", pokemon.name),
+                ("id", pokemon.id.to_string()),
+                ("description", pokemon.description),
+                (
+                    "habitat",
+                    pokemon.habitat.unwrap_or_else(|| "unknown".into()),
+                ),
+                ("legendary", pokemon.is_legendary.to_string()),
+            ];
+            rows.extend(style.map(|style| ("style", style.to_string())));
+            Ok(table(&rows))
+        }
+        Format::Json => {
+            let mut pokemon = serde_json::to_value(&pokemon).map_err(|error| error.to_string())?;
+            if let (Some(style), Some(pokemon)) = (style, pokemon.as_object_mut()) {
+                pokemon.insert("style".into(), style.name().into());
+            }
+            json(&pokemon)
+        }
+    }
+}
+
+fn list(names: Vec<String>, format: Format) -> Result<String, String> {
+    match format {
+        Format::Table => Ok(names.join("\n")),
+        Format::Json => json(&names),
+    }
+}
+
+fn json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|error| error.to_string())
+}
+
+/// Two columns, where the values line up
+fn table(rows: &[(&str, String)]) -> String {
+    let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(key, value)| format!("{:width$}  {}", key, value, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mocks::*;
+    use crate::server::ServerSettings;
+    use crate::Settings;
+    use pretty_assertions::assert_eq;
+
+    async fn setup() -> (Clients, MockPokeApi, MockTranslationApi) {
+        let poke_mock = setup_poke_api().await;
+        let translation_mock = setup_translation_api().await;
+        let clients = Clients::from(&Settings {
+            server: ServerSettings::default(),
+            poke_api: poke_mock.settings().clone(),
+            translation_api: translation_mock.settings().clone(),
+        });
+
+        (clients, poke_mock, translation_mock)
+    }
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from([&["oak"], args].concat()).expect("Invalid arguments")
+    }
+
+    #[test]
+    fn parses_the_command_line() {
+        let cli = parse(&["--config", "poke.yml", "--profile=production"]);
+        assert_eq!(
+            cli.args,
+            Args {
+                config: Some("poke.yml".into()),
+                profile: Some("production".into()),
+            }
+        );
+        assert_eq!(cli.command, None);
+
+        let cli = parse(&["config", "check", "--config", "poke.yml"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Config {
+                command: ConfigCommand::Check
+            })
+        );
+
+        let cli = parse(&["translate", "yoda", "Hello", "there", "--format", "json"]);
+        assert_eq!(cli.format, Format::Json);
+        assert_eq!(
+            cli.command,
+            Some(Command::Query(Query::Translate {
+                style: Language::Yoda,
+                text: vec!["Hello".into(), "there".into()],
+            }))
+        );
+
+        assert!(Cli::try_parse_from(["oak", "translate", "elvish", "Hello"]).is_err());
+        assert!(Cli::try_parse_from(["oak", "config", "fix"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn looks_up_a_pokemon_as_a_table() {
+        let (clients, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
+
+        let lookup = Query::Lookup {
+            name: "mewtwo".into(),
+            translated: false,
+        };
+        assert_eq!(
+            run(lookup, Format::Table, &clients).await,
+            Ok("name         mewtwo
+id           150
+description  It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.
+habitat      rare
+legendary    true"
+                .to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn looks_up_a_pokemon_without_a_habitat() {
+        let (clients, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("lucario", RAW_LUCARIO).await;
+
+        let lookup = Query::Lookup {
+            name: "lucario".into(),
+            translated: false,
+        };
+        let lucario = run(lookup, Format::Table, &clients)
+            .await
+            .expect("Unable to look up lucario");
+        assert!(lucario.contains("habitat      unknown\n"));
+    }
+
+    #[tokio::test]
+    async fn looks_up_a_translated_pokemon_as_json() {
+        let (clients, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        let lookup = Query::Lookup {
+            name: "diglett".into(),
+            translated: true,
+        };
+        let diglett = run(lookup, Format::Json, &clients)
+            .await
+            .expect("Unable to look up diglett");
+        let diglett = serde_json::from_str::<serde_json::Value>(&diglett).unwrap();
+
+        assert_eq!(diglett["style"], "yoda");
+        assert_eq!(
+            diglett["description"],
+            "On plant roots,  lives about one yard underground where it feeds.Above ground,  it sometimes appears."
+        );
+    }
+
+    #[tokio::test]
+    async fn suggests_names_for_pokemon_that_were_not_found() {
+        let (clients, poke_mock, _) = setup().await;
+
+        poke_mock.has_species_index(RAW_SPECIES_INDEX).await;
+        poke_mock.no_pokemon_exist().await;
+
+        let lookup = Query::Lookup {
+            name: "pikchu".into(),
+            translated: false,
+        };
+        assert_eq!(
+            run(lookup, Format::Table, &clients).await,
+            Err("Unable to find 'pikchu', did you mean pikachu, raichu?".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn translates_text() {
+        let (clients, _, translation_mock) = setup().await;
+
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        let translate = Query::Translate {
+            style: Language::Yoda,
+            text: vec!["Lives about one yard underground.".into()],
+        };
+        assert_eq!(
+            run(translate, Format::Table, &clients).await,
+            Ok("On plant roots,  lives about one yard underground where it feeds.Above ground,  it sometimes appears.".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_cave_pokemon_and_legendaries() {
+        let (clients, poke_mock, _) = setup().await;
+
+        poke_mock.has_habitat("cave", RAW_CAVE_HABITAT).await;
+        poke_mock.has_legendaries(RAW_LEGENDARIES).await;
+
+        let habitat = Query::Habitat {
+            name: "cave".into(),
+        };
+        assert_eq!(
+            run(habitat, Format::Table, &clients).await,
+            Ok("diglett\nonix\nzubat".to_string())
+        );
+        assert_eq!(
+            run(Query::Legendaries, Format::Json, &clients)
+                .await
+                .map(|names| serde_json::from_str::<Vec<String>>(&names).unwrap()),
+            Ok(vec![
+                "articuno".to_string(),
+                "mewtwo".to_string(),
+                "moltres".to_string(),
+                "zapdos".to_string()
+            ])
+        );
+    }
+}
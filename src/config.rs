@@ -0,0 +1,773 @@
+//! Settings come from the YAML file given with `--config`, then the profile picked with `--profile`
+//! or `APP_PROFILE`, then the `APP_*` environment variables, each overriding the one before.
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::path::{Path, PathBuf};
+
+use crate::Settings;
+
+/// How the value of an environment variable ends up in the settings
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    /// Taken as it is, e.g. `https://pokeapi.co` or `10s`
+    Text,
+    /// A comma-separated list, e.g. `de,en`
+    List,
+    /// Numbers, booleans, or whole structures such as the rules in JSON
+    Yaml,
+}
+
+/// The environment variables that can override individual settings
+const OVERRIDES: &[(&str, &[&str], Format)] = &[
+    ("APP_SERVER_ADDRESS", &["server", "address"], Format::Text),
+    ("APP_SERVER_PORT", &["server", "port"], Format::Yaml),
+    ("APP_SERVER_WORKERS", &["server", "workers"], Format::Yaml),
+    (
+        "APP_SERVER_KEEP_ALIVE",
+        &["server", "keep_alive"],
+        Format::Text,
+    ),
+    (
+        "APP_SERVER_TLS_CERTS",
+        &["server", "tls", "certs"],
+        Format::Text,
+    ),
+    (
+        "APP_SERVER_TLS_KEY",
+        &["server", "tls", "key"],
+        Format::Text,
+    ),
+    (
+        "APP_POKE_API_BASE_URL",
+        &["poke_api", "base_url"],
+        Format::Text,
+    ),
+    (
+        "APP_POKE_API_GRAPHQL_URL",
+        &["poke_api", "graphql_url"],
+        Format::Text,
+    ),
+    (
+        "APP_POKE_API_TIMEOUT",
+        &["poke_api", "timeout"],
+        Format::Text,
+    ),
+    (
+        "APP_POKE_API_LANGUAGES",
+        &["poke_api", "languages"],
+        Format::List,
+    ),
+    (
+        "APP_POKE_API_VERSION_STRATEGY",
+        &["poke_api", "version_strategy"],
+        Format::Text,
+    ),
+    (
+        "APP_POKE_API_CACHE_CAPACITY",
+        &["poke_api", "cache", "capacity"],
+        Format::Yaml,
+    ),
+    (
+        "APP_POKE_API_CACHE_TTL",
+        &["poke_api", "cache", "ttl"],
+        Format::Text,
+    ),
+    (
+        "APP_POKE_API_CACHE_NEGATIVE_TTL",
+        &["poke_api", "cache", "negative_ttl"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_BASE_URL",
+        &["translation_api", "base_url"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_TIMEOUT",
+        &["translation_api", "timeout"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_CACHE_FILE",
+        &["translation_api", "cache_file"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_RULES",
+        &["translation_api", "rules"],
+        Format::Yaml,
+    ),
+    (
+        "APP_TRANSLATION_API_QUEUE_INTERVAL",
+        &["translation_api", "queue", "interval"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_QUEUE_FILE",
+        &["translation_api", "queue", "file"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_SECRET",
+        &["translation_api", "api_secret"],
+        Format::Text,
+    ),
+    (
+        "APP_TRANSLATION_API_SECRET_FILE",
+        &["translation_api", "api_secret_file"],
+        Format::Text,
+    ),
+    (
+        "APP_LIBRE_TRANSLATE_BASE_URL",
+        &["translation_api", "libre_translate", "base_url"],
+        Format::Text,
+    ),
+    (
+        "APP_LIBRE_TRANSLATE_TIMEOUT",
+        &["translation_api", "libre_translate", "timeout"],
+        Format::Text,
+    ),
+    (
+        "APP_LIBRE_TRANSLATE_API_KEY",
+        &["translation_api", "libre_translate", "api_key"],
+        Format::Text,
+    ),
+];
+
+/// What a setting has to look like
+#[derive(Clone, Copy, Debug)]
+enum Check {
+    /// An absolute http or https URL
+    Url,
+    /// A duration such as `10s`
+    Duration,
+    /// A duration that is longer than 0s, as requests would fail right away otherwise
+    Timeout,
+    /// A duration in whole seconds, as that is all Rocket takes
+    Seconds,
+    /// A file that exists
+    File,
+}
+
+/// The settings that are checked before the rest is deserialized, so all problems with them are
+/// reported at once. They only have to be present if the section they are in is.
+const CHECKS: &[(&[&str], &str, bool, Check)] = &[
+    (&["server"], "keep_alive", false, Check::Seconds),
+    (&["server", "tls"], "certs", true, Check::File),
+    (&["server", "tls"], "key", true, Check::File),
+    (&["poke_api"], "base_url", true, Check::Url),
+    (&["poke_api"], "graphql_url", false, Check::Url),
+    (&["poke_api"], "timeout", true, Check::Timeout),
+    (&["poke_api", "cache"], "ttl", false, Check::Duration),
+    (
+        &["poke_api", "cache"],
+        "negative_ttl",
+        false,
+        Check::Duration,
+    ),
+    (&["translation_api"], "base_url", true, Check::Url),
+    (&["translation_api"], "timeout", true, Check::Timeout),
+    (&["translation_api"], "api_secret_file", false, Check::File),
+    (
+        &["translation_api", "libre_translate"],
+        "base_url",
+        true,
+        Check::Url,
+    ),
+    (
+        &["translation_api", "libre_translate"],
+        "timeout",
+        false,
+        Check::Timeout,
+    ),
+    (
+        &["translation_api", "queue"],
+        "interval",
+        true,
+        Check::Duration,
+    ),
+];
+
+/// Sections that don't have to be there
+const OPTIONAL_SECTIONS: &[&[&str]] = &[
+    &["server"],
+    &["server", "tls"],
+    &["poke_api", "cache"],
+    &["translation_api", "libre_translate"],
+    &["translation_api", "queue"],
+];
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Unable to read {}: {1}", .0.display())]
+    Read(PathBuf, std::io::Error),
+    #[error("{} is not valid YAML: {1}", .0.display())]
+    Yaml(PathBuf, serde_yaml::Error),
+    #[error("There is no profile called '{0}'")]
+    UnknownProfile(String),
+    #[error("Invalid configuration:{}", .0.iter().map(|problem| format!("\n  - {}", problem)).collect::<String>())]
+    Invalid(Vec<String>),
+}
+
+/// Where to find the settings, shared by all commands
+#[derive(clap::Args, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Args {
+    /// The YAML file with the settings, otherwise they all come from `APP_*` environment variables
+    #[arg(long, global = true)]
+    pub(crate) config: Option<PathBuf>,
+    /// The profile of the configuration file to use, also read from `APP_PROFILE`
+    #[arg(long, global = true)]
+    pub(crate) profile: Option<String>,
+}
+
+impl Args {
+    pub(crate) fn settings(&self) -> Result<Settings, Error> {
+        let profile = self
+            .profile
+            .clone()
+            .or_else(|| std::env::var("APP_PROFILE").ok());
+        load(self.config.as_deref(), profile.as_deref(), |name| {
+            std::env::var(name).ok()
+        })
+    }
+}
+
+fn load<E>(file: Option<&Path>, profile: Option<&str>, env: E) -> Result<Settings, Error>
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let mut settings = match file {
+        Some(path) => read(path)?,
+        None => Value::Mapping(Mapping::new()),
+    };
+
+    let profiles = match &mut settings {
+        Value::Mapping(settings) => settings.remove(&Value::from("profiles")),
+        _ => None,
+    };
+    if let Some(name) = profile {
+        let overrides = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .cloned()
+            .ok_or_else(|| Error::UnknownProfile(name.to_string()))?;
+        merge(&mut settings, overrides);
+    }
+
+    let mut problems = Vec::new();
+    for (name, path, format) in OVERRIDES {
+        if let Some(value) = env(name) {
+            let value = match format {
+                Format::Text => Value::String(value),
+                Format::List => Value::Sequence(
+                    value
+                        .split(',')
+                        .map(|item| Value::String(item.trim().to_string()))
+                        .collect(),
+                ),
+                Format::Yaml => match serde_yaml::from_str(&value) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        problems.push(format!("{} is not valid YAML: {}", name, error));
+                        continue;
+                    }
+                },
+            };
+            merge(&mut settings, nested(path, value));
+        }
+    }
+
+    // Each section is read on its own, so a broken one doesn't hide the problems of the others
+    let server = section(&settings, "server", &mut problems);
+    let poke_api = section(&settings, "poke_api", &mut problems);
+    let translation_api = section(&settings, "translation_api", &mut problems);
+    let mut settings = match (server, poke_api, translation_api) {
+        (Some(server), Some(poke_api), Some(translation_api)) if problems.is_empty() => Settings {
+            server,
+            poke_api,
+            translation_api,
+        },
+        _ => return Err(Error::Invalid(problems)),
+    };
+    settings
+        .translation_api
+        .read_secret_file()
+        .map_err(|problem| Error::Invalid(vec![problem]))?;
+    Ok(settings)
+}
+
+/// Deserializes one section of the settings unless its checks already found problems, which
+/// explain more than the first error serde runs into would.
+fn section<T: DeserializeOwned>(
+    settings: &Value,
+    name: &str,
+    problems: &mut Vec<String>,
+) -> Option<T> {
+    let checked = validate(settings, name);
+    if !checked.is_empty() {
+        problems.extend(checked);
+        return None;
+    }
+
+    let value = match settings.get(name) {
+        Some(Value::Null) | None => Value::Mapping(Mapping::new()),
+        Some(value) => value.clone(),
+    };
+    serde_yaml::from_value(value)
+        .map_err(|error| problems.push(format!("{}: {}", name, error)))
+        .ok()
+}
+
+/// Everything that is wrong with the checked settings of a section, rather than just the first
+/// problem
+fn validate(settings: &Value, name: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let lookup = |path: &[&str]| {
+        path.iter()
+            .try_fold(settings, |value, key| value.get(*key))
+            .filter(|value| !value.is_null())
+    };
+
+    for (section, key, required, check) in CHECKS.iter().filter(|(section, ..)| section[0] == name)
+    {
+        let name = format!("{}.{}", section.join("."), key);
+        let section_is_missing = lookup(section).is_none();
+        if section_is_missing && OPTIONAL_SECTIONS.contains(section) {
+            continue;
+        }
+
+        let value = match lookup(&[section, &[*key][..]].concat()) {
+            Some(value) => value,
+            None if *required => {
+                problems.push(format!("{} is missing", name));
+                continue;
+            }
+            None => continue,
+        };
+        let value = match value {
+            Value::String(value) => value,
+            // Such as a number, which YAML reads `timeout: 10` as
+            _ => {
+                problems.push(format!("{}: {}", name, check.expected()));
+                continue;
+            }
+        };
+
+        if let Err(problem) = check.run(value) {
+            problems.push(format!("{}: {}", name, problem));
+        }
+    }
+
+    problems
+}
+
+impl Check {
+    /// What to write instead of a value that isn't text at all
+    fn expected(self) -> &'static str {
+        match self {
+            Check::Url => "use an http or https URL such as https://pokeapi.co",
+            Check::Duration | Check::Timeout | Check::Seconds => "use a duration such as 10s",
+            Check::File => "use the path of a file",
+        }
+    }
+
+    fn run(self, value: &str) -> Result<(), String> {
+        match self {
+            Check::Url => {
+                let url = reqwest::Url::parse(value)
+                    .map_err(|error| format!("'{}' is not a URL: {}", value, error))?;
+                match url.scheme() {
+                    "http" | "https" => Ok(()),
+                    _ => Err(format!("'{}' has to be an http or https URL", value)),
+                }
+            }
+            Check::Duration => duration(value).map(|_| ()),
+            Check::File if Path::new(value).is_file() => Ok(()),
+            Check::File => Err(format!("'{}' does not exist", value)),
+            Check::Timeout => match duration(value)? {
+                timeout if timeout.is_zero() => Err("has to be longer than 0s".into()),
+                _ => Ok(()),
+            },
+            Check::Seconds => match duration(value)? {
+                seconds if seconds.subsec_nanos() > 0 => {
+                    Err(format!("'{}' has to be whole seconds such as 5s", value))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+fn duration(value: &str) -> Result<std::time::Duration, String> {
+    humantime::parse_duration(value)
+        .map_err(|error| format!("'{}' is not a duration such as 10s: {}", value, error))
+}
+
+fn read(path: &Path) -> Result<Value, Error> {
+    let content =
+        std::fs::read_to_string(path).map_err(|error| Error::Read(path.to_path_buf(), error))?;
+    let settings =
+        serde_yaml::from_str(&content).map_err(|error| Error::Yaml(path.to_path_buf(), error))?;
+
+    // An empty file has no settings rather than being `null`
+    match settings {
+        Value::Null => Ok(Value::Mapping(Mapping::new())),
+        settings => Ok(settings),
+    }
+}
+
+/// Wraps the value in a mapping for each key of the path, e.g. `{poke_api: {timeout: 10s}}`
+fn nested(path: &[&str], value: Value) -> Value {
+    path.iter().rev().fold(value, |value, key| {
+        let mut mapping = Mapping::new();
+        mapping.insert(Value::from(*key), value);
+        Value::Mapping(mapping)
+    })
+}
+
+/// Mappings are merged key by key, everything else such as lists is replaced as a whole.
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Mapping(base), Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::Secret;
+    use crate::translation::Language;
+    use claim::assert_matches;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn config(content: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("poke.yml");
+        std::fs::write(&path, content).expect("Unable to write the configuration");
+        (dir, path)
+    }
+
+    #[test]
+    fn reads_the_bundled_configuration() {
+        let settings =
+            load(Some(Path::new("poke.yml")), None, env(&[])).expect("poke.yml should be valid");
+
+        assert_eq!(settings.poke_api.base_url, "https://pokeapi.co");
+        assert_eq!(settings.poke_api.timeout, Duration::from_secs(10));
+        assert_eq!(
+            settings.poke_api.cache.ttl,
+            Duration::from_secs(24 * 60 * 60)
+        );
+        assert_eq!(settings.translation_api.rules.len(), 2);
+    }
+
+    #[test]
+    fn environment_variables_override_single_settings() {
+        let (_dir, path) = config(
+            r#"
+            poke_api:
+              base_url: https://pokeapi.co
+              timeout: 10s
+              cache:
+                capacity: 10
+            translation_api:
+              base_url: https://api.funtranslations.com
+              timeout: 10s
+            "#,
+        );
+
+        let settings = load(
+            Some(&path),
+            None,
+            env(&[
+                ("APP_POKE_API_TIMEOUT", "2s"),
+                ("APP_POKE_API_LANGUAGES", "de, en"),
+                ("APP_POKE_API_CACHE_TTL", "1h"),
+                ("APP_TRANSLATION_API_RULES", r#"[{"style": "pirate"}]"#),
+            ]),
+        )
+        .expect("Unable to load the settings");
+
+        assert_eq!(settings.poke_api.base_url, "https://pokeapi.co");
+        assert_eq!(settings.poke_api.timeout, Duration::from_secs(2));
+        assert_eq!(settings.poke_api.languages, vec!["de", "en"]);
+        assert_eq!(settings.poke_api.cache.capacity, 10);
+        assert_eq!(settings.poke_api.cache.ttl, Duration::from_secs(60 * 60));
+        assert_eq!(settings.translation_api.rules[0].style, Language::Pirate);
+    }
+
+    #[test]
+    fn works_without_a_file() {
+        let settings = load(
+            None,
+            None,
+            env(&[
+                ("APP_POKE_API_BASE_URL", "https://pokeapi.co"),
+                ("APP_POKE_API_TIMEOUT", "10s"),
+                (
+                    "APP_TRANSLATION_API_BASE_URL",
+                    "https://api.funtranslations.com",
+                ),
+                ("APP_TRANSLATION_API_TIMEOUT", "10s"),
+                ("APP_LIBRE_TRANSLATE_BASE_URL", "http://localhost:5000"),
+            ]),
+        )
+        .expect("Unable to load the settings");
+
+        assert_eq!(settings.poke_api.languages, vec!["en"]);
+        assert_eq!(
+            settings
+                .translation_api
+                .libre_translate
+                .map(|libre| libre.base_url),
+            Some("http://localhost:5000".to_string())
+        );
+    }
+
+    #[test]
+    fn profiles_override_the_rest_of_the_file() {
+        let (_dir, path) = config(
+            r#"
+            poke_api:
+              base_url: https://pokeapi.co
+              timeout: 10s
+            translation_api:
+              base_url: https://api.funtranslations.com
+              timeout: 10s
+            profiles:
+              test:
+                poke_api:
+                  base_url: http://localhost:8080
+              production:
+                translation_api:
+                  cache_file: /var/lib/oak/translations.jsonl
+            "#,
+        );
+
+        let settings =
+            load(Some(&path), Some("test"), env(&[])).expect("Unable to load the settings");
+        assert_eq!(settings.poke_api.base_url, "http://localhost:8080");
+        assert_eq!(settings.poke_api.timeout, Duration::from_secs(10));
+        assert_eq!(settings.translation_api.cache_file, None);
+
+        let settings = load(
+            Some(&path),
+            Some("production"),
+            env(&[("APP_TRANSLATION_API_CACHE_FILE", "translations.jsonl")]),
+        )
+        .expect("Unable to load the settings");
+        assert_eq!(settings.poke_api.base_url, "https://pokeapi.co");
+        assert_eq!(
+            settings.translation_api.cache_file,
+            Some("translations.jsonl".into())
+        );
+
+        assert_matches!(
+            load(Some(&path), Some("staging"), env(&[])),
+            Err(Error::UnknownProfile(_))
+        );
+    }
+
+    #[test]
+    fn reports_all_problems_at_once() {
+        let (_dir, path) = config(
+            r#"
+            poke_api:
+              base_url: ftp://pokeapi.co
+              timeout: 0s
+              cache:
+                ttl: a day
+            translation_api:
+              base_url: api.funtranslations.com
+              libre_translate:
+                timeout: 10s
+            "#,
+        );
+
+        let error = load(
+            Some(&path),
+            None,
+            env(&[("APP_TRANSLATION_API_RULES", "[{")]),
+        )
+        .expect_err("The settings should be invalid");
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration:
+  - APP_TRANSLATION_API_RULES is not valid YAML: while parsing a node, did not find expected node content at line 2 column 1
+  - poke_api.base_url: 'ftp://pokeapi.co' has to be an http or https URL
+  - poke_api.timeout: has to be longer than 0s
+  - poke_api.cache.ttl: 'a day' is not a duration such as 10s: expected number at 0
+  - translation_api.base_url: 'api.funtranslations.com' is not a URL: relative URL without a base
+  - translation_api.timeout is missing
+  - translation_api.libre_translate.base_url is missing"
+        );
+    }
+
+    #[test]
+    fn reports_the_problems_of_every_section() {
+        let (_dir, path) = config(
+            r#"
+            server:
+              keep_alive: 1500ms
+            poke_api:
+              base_url: https://pokeapi.co
+              timeout: 10s
+              version_strategy: newest
+            translation_api:
+              base_url: https://api.funtranslations.com
+              timeout: 10s
+              rules:
+                - style: not-a-style
+            "#,
+        );
+
+        let problems = match load(Some(&path), None, env(&[])) {
+            Err(Error::Invalid(problems)) => problems,
+            other => panic!(
+                "The settings should be invalid, got {:?}",
+                other.map(|_| ())
+            ),
+        };
+
+        assert_eq!(problems.len(), 3);
+        assert_eq!(
+            problems[0],
+            "server.keep_alive: '1500ms' has to be whole seconds such as 5s"
+        );
+        assert_eq!(
+            problems[1],
+            "poke_api: unknown variant `newest`, expected one of `first`, `earliest`, `latest`, `random`"
+        );
+        assert!(problems[2]
+            .starts_with("translation_api: 'not-a-style' is not a known translation style"));
+    }
+
+    #[test]
+    fn tells_what_to_write_instead_of_a_number() {
+        let (_dir, path) = config(
+            r#"
+            poke_api:
+              base_url: https://pokeapi.co
+              graphql_url: 42
+              timeout: 10
+            translation_api:
+              base_url: https://api.funtranslations.com
+              timeout: 10s
+            "#,
+        );
+
+        let error = load(Some(&path), None, env(&[])).expect_err("The settings should be invalid");
+        assert_matches!(
+            error,
+            Error::Invalid(problems) if problems == vec![
+                "poke_api.graphql_url: use an http or https URL such as https://pokeapi.co".to_string(),
+                "poke_api.timeout: use a duration such as 10s".to_string(),
+            ]
+        );
+
+        let error = load(
+            Some(Path::new("poke.yml")),
+            None,
+            env(&[("APP_POKE_API_GRAPHQL_URL", "pokeapi.co/graphql")]),
+        )
+        .expect_err("The settings should be invalid");
+        assert_matches!(
+            error,
+            Error::Invalid(problems) if problems == vec![
+                "poke_api.graphql_url: 'pokeapi.co/graphql' is not a URL: relative URL without a base".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_server_settings_can_be_overridden_too() {
+        let vars = [
+            ("APP_SERVER_ADDRESS", "0.0.0.0"),
+            ("APP_SERVER_PORT", "9000"),
+            ("APP_SERVER_TLS_CERTS", "/does/not/exist.pem"),
+            ("APP_SERVER_KEEP_ALIVE", "1500ms"),
+        ];
+
+        let error = load(Some(Path::new("poke.yml")), None, env(&vars))
+            .expect_err("The settings should be invalid");
+        assert_matches!(
+            error,
+            Error::Invalid(problems) if problems == vec![
+                "server.keep_alive: '1500ms' has to be whole seconds such as 5s".to_string(),
+                "server.tls.certs: '/does/not/exist.pem' does not exist".to_string(),
+                "server.tls.key is missing".to_string(),
+            ]
+        );
+
+        let settings = load(Some(Path::new("poke.yml")), None, env(&vars[..2]))
+            .expect("Unable to load the settings");
+        assert_eq!(settings.server.address, Some([0, 0, 0, 0].into()));
+        assert_eq!(settings.server.port, Some(9000));
+    }
+
+    #[test]
+    fn reads_the_secret_file_while_loading() {
+        let (dir, path) = config(
+            r#"
+            poke_api:
+              base_url: https://pokeapi.co
+              timeout: 10s
+            translation_api:
+              base_url: https://api.funtranslations.com
+              timeout: 10s
+            "#,
+        );
+        let secret_file = dir.path().join("funtranslations");
+        std::fs::write(&secret_file, "very-secret\n").expect("Unable to write the secret");
+
+        let secret_file = secret_file.display().to_string();
+        let settings = load(
+            Some(&path),
+            None,
+            env(&[("APP_TRANSLATION_API_SECRET_FILE", &secret_file)]),
+        )
+        .expect("Unable to load the settings");
+
+        assert_eq!(
+            settings.translation_api.api_secret,
+            Some(Secret::new("very-secret"))
+        );
+    }
+
+    #[test]
+    fn missing_sections_are_reported() {
+        let error = load(None, None, env(&[("APP_POKE_API_TIMEOUT", "10s")]))
+            .expect_err("The settings should be invalid");
+
+        assert_matches!(
+            error,
+            Error::Invalid(problems) if problems == vec![
+                "poke_api.base_url is missing".to_string(),
+                "translation_api.base_url is missing".to_string(),
+                "translation_api.timeout is missing".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::secret::Secret;
+use crate::translation::{Error, Kind, Language, Translator};
+
+/// PokeAPI descriptions are always fetched in English before being translated
+const SOURCE_LANGUAGE: &str = "en";
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct LibreTranslateSettings {
+    pub(crate) base_url: String,
+    #[serde(with = "humantime_serde", default = "default_timeout")]
+    pub(crate) timeout: Duration,
+    /// Only needed for instances that require one, such as https://libretranslate.com
+    #[serde(default)]
+    pub(crate) api_key: Option<Secret>,
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Translated {
+    translated_text: String,
+}
+
+/// Real translations into natural languages from a LibreTranslate instance,
+/// see https://github.com/LibreTranslate/LibreTranslate
+#[derive(Debug)]
+pub(crate) struct LibreTranslate {
+    client: Client,
+    domain: String,
+    api_key: Option<Secret>,
+}
+
+impl From<LibreTranslateSettings> for LibreTranslate {
+    fn from(settings: LibreTranslateSettings) -> Self {
+        let client = Client::builder()
+            .timeout(settings.timeout)
+            .build()
+            .expect("failed to construct a viable LibreTranslate client");
+        LibreTranslate {
+            client,
+            domain: settings.base_url,
+            api_key: settings.api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl Translator for LibreTranslate {
+    fn supports(&self, language: Language) -> bool {
+        matches!(language.kind(), Kind::Natural(_))
+    }
+
+    async fn translate(&self, text: &str, language: Language) -> Result<String, Error> {
+        #[derive(Serialize)]
+        struct Request<'a> {
+            q: &'a str,
+            source: &'static str,
+            target: &'static str,
+            format: &'static str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            api_key: Option<&'a str>,
+        }
+
+        let target = match language.kind() {
+            Kind::Natural(code) => code,
+            Kind::Fun => return Err(Error::Unsupported(language)),
+        };
+
+        let translated = self
+            .client
+            .post(format!("{}/translate", self.domain))
+            .json(&Request {
+                q: text,
+                source: SOURCE_LANGUAGE,
+                target,
+                format: "text",
+                api_key: self.api_key.as_ref().map(Secret::expose),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Translated>()
+            .await?;
+
+        Ok(translated.translated_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mocks;
+    use claim::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn translates_descriptions_to_german() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate_naturally("de", mocks::BULBASAUR_IN_GERMAN)
+            .await;
+
+        let translated = mock_server
+            .client()
+            .translate(
+                "A strange seed was planted on its back at birth.",
+                Language::German,
+            )
+            .await
+            .expect("Unable to get translation");
+
+        assert_eq!(
+            translated.text,
+            "Bei der Geburt wurde ihm ein seltsamer Samen auf den Rücken gepflanzt.".to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_an_error_for_bad_json() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server.can_translate_naturally("fr", "{ }").await;
+
+        let err = mock_server
+            .client()
+            .translate("This is fantastic", Language::French)
+            .await
+            .expect_err("Request should have failed due to bad JSON");
+
+        assert_matches!(err, Error::BadJson)
+    }
+}
@@ -0,0 +1,210 @@
+//! Rough translations that don't need FunTranslations, for when it is unavailable.
+//! They are nowhere near as good, but better than no translation at all.
+
+use crate::translation::Language;
+
+/// Verbs that Yoda likes to put at the end of a sentence
+const AUXILIARIES: &[&str] = &[
+    "is", "are", "was", "were", "can", "could", "will", "would", "has", "have", "had", "must",
+    "should", "may", "might",
+];
+
+const PREPOSITIONS: &[&str] = &[
+    "on", "in", "at", "above", "under", "below", "with", "from", "into", "onto", "over", "near",
+    "behind", "inside", "through", "across", "during", "for", "to", "by",
+];
+
+const SHAKESPEAREAN: &[(&str, &str)] = &[
+    ("you", "thee"),
+    ("your", "thy"),
+    ("yours", "thine"),
+    ("you're", "thou art"),
+    ("are", "art"),
+    ("was", "wast"),
+    ("were", "wert"),
+    ("has", "hath"),
+    ("does", "doth"),
+    ("it's", "'tis"),
+    ("before", "ere"),
+    ("often", "oft"),
+    ("over", "o'er"),
+    ("never", "ne'er"),
+    ("even", "e'en"),
+    ("yes", "aye"),
+    ("no", "nay"),
+    ("hello", "good morrow"),
+    ("hi", "hail"),
+    ("friend", "good sir"),
+    ("perhaps", "perchance"),
+    ("why", "wherefore"),
+    ("maybe", "mayhap"),
+    ("here", "hither"),
+    ("there", "thither"),
+    ("away", "hence"),
+];
+
+/// Translates `text` without asking FunTranslations, if we know how to for that language.
+pub(crate) fn translate(text: &str, language: Language) -> Option<String> {
+    let translate_sentence = match language {
+        Language::Yoda => yoda,
+        Language::Shakespear => shakespeare,
+        _ => return None,
+    };
+
+    let translated = sentences(text)
+        .into_iter()
+        .map(|(sentence, punctuation)| format!("{}{}", translate_sentence(sentence), punctuation))
+        .collect::<Vec<_>>();
+    Some(translated.join(" "))
+}
+
+/// Splits text into sentences and the punctuation that ended them
+fn sentences(text: &str) -> Vec<(&str, char)> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        let ends_sentence = matches!(c, '.' | '!' | '?')
+            && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if ends_sentence {
+            let sentence = text[start..index].trim();
+            if !sentence.is_empty() {
+                sentences.push((sentence, c));
+            }
+            start = index + c.len_utf8();
+        }
+    }
+
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        sentences.push((rest, '.'));
+    }
+    sentences
+}
+
+/// "It was created by a scientist" becomes "Created by a scientist, it was", and
+/// "It sometimes appears above ground" becomes "Above ground, it sometimes appears".
+fn yoda(sentence: &str) -> String {
+    let words = sentence.split_whitespace().collect::<Vec<_>>();
+
+    // Only short subjects such as "It" or "This Pokemon", otherwise the result is confusing
+    let auxiliary = words
+        .iter()
+        .take(4)
+        .position(|word| AUXILIARIES.contains(&word.to_lowercase().as_str()))
+        .filter(|&position| position > 0 && position < words.len() - 1);
+    if let Some(position) = auxiliary {
+        let subject = &words[..=position];
+        let rest = &words[position + 1..];
+        return format!(
+            "{}, {}",
+            capitalize(&rest.join(" ")),
+            uncapitalize(&subject.join(" "))
+        );
+    }
+
+    let preposition = words
+        .iter()
+        .rposition(|word| PREPOSITIONS.contains(&word.to_lowercase().as_str()))
+        .filter(|&position| position > 0 && position < words.len() - 1);
+    if let Some(position) = preposition {
+        let front = &words[..position];
+        let phrase = &words[position..];
+        return format!(
+            "{}, {}",
+            capitalize(&phrase.join(" ")),
+            uncapitalize(&front.join(" "))
+        );
+    }
+
+    sentence.to_string()
+}
+
+fn shakespeare(sentence: &str) -> String {
+    let words = sentence.split_whitespace().map(|word| {
+        let trimmed = word.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+        let punctuation = &word[trimmed.len()..];
+
+        match SHAKESPEAREAN
+            .iter()
+            .find(|(modern, _)| modern.eq_ignore_ascii_case(trimmed))
+        {
+            Some((_, old)) if starts_uppercase(trimmed) => {
+                format!("{}{}", capitalize(old), punctuation)
+            }
+            Some((_, old)) => format!("{}{}", old, punctuation),
+            None => word.to_string(),
+        }
+    });
+
+    words.collect::<Vec<_>>().join(" ")
+}
+
+fn starts_uppercase(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_uppercase)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Lowercases the first letter unless the first word looks like a name or an acronym
+fn uncapitalize(text: &str) -> String {
+    let first_word = text.split_whitespace().next().unwrap_or_default();
+    let rest_is_lowercase = first_word.chars().skip(1).all(|c| !c.is_uppercase());
+    if first_word == "I" || !rest_is_lowercase {
+        return text.to_string();
+    }
+
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn moves_the_verb_to_the_end_like_yoda() {
+        assert_eq!(
+            translate("It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.", Language::Yoda),
+            Some("Created by a scientist after years of horrific gene splicing and DNA engineering experiments, it was.".to_string())
+        );
+    }
+
+    #[test]
+    fn moves_the_last_prepositional_phrase_to_the_front_like_yoda() {
+        assert_eq!(
+            translate("Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground.", Language::Yoda),
+            Some("On plant roots, lives about one yard underground where it feeds. Above ground, it sometimes appears.".to_string())
+        );
+    }
+
+    #[test]
+    fn substitutes_shakespearean_words() {
+        assert_eq!(
+            translate(
+                "A strange seed was planted on its back at birth. Are you my friend?",
+                Language::Shakespear
+            ),
+            Some(
+                "A strange seed wast planted on its back at birth. Art thee my good sir?"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn only_knows_yoda_and_shakespeare() {
+        assert_eq!(translate("Yo ho ho", Language::Pirate), None);
+    }
+}
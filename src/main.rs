@@ -1,44 +1,42 @@
-use std::time::Duration;
-
+use cli::{Cli, Command, ConfigCommand};
 use pokeapi::{PokeApiSettings, PokeClient};
-use server::rocket;
+use queue::TranslationQueue;
+use reload::Reloadable;
+use rules::Rules;
+use server::{rocket, Clients, ServerSettings};
 use translation::{TranslationClient, TranslationSettings};
 
+use clap::Parser;
 use serde::Deserialize;
+use std::fmt::Display;
 
+mod cache;
+mod cli;
+mod config;
+mod libretranslate;
+mod local;
 mod pokeapi;
+mod queue;
+mod reload;
+mod rules;
+mod secret;
 mod server;
+mod store;
 mod translation;
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes, clippy::to_string_in_format_args)]
 mod mocks;
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 struct Settings {
+    #[serde(default)]
+    server: ServerSettings,
     poke_api: PokeApiSettings,
     translation_api: TranslationSettings,
 }
 
 impl Settings {
-    fn from_env() -> Self {
-        let poke_api_base_url = env_var("APP_POKE_API_BASE_URL");
-        let poke_api_timeout = env_var("APP_POKE_API_TIMEOUT");
-
-        let translation_api_base_url = env_var("APP_TRANSLATION_API_BASE_URL");
-        let translation_api_timeout = env_var("APP_TRANSLATION_API_TIMEOUT");
-
-        Settings {
-            poke_api: PokeApiSettings {
-                base_url: poke_api_base_url,
-                timeout: parse(poke_api_timeout).unwrap(),
-            },
-            translation_api: TranslationSettings {
-                base_url: translation_api_base_url,
-                timeout: parse(translation_api_timeout).unwrap(),
-            },
-        }
-    }
-
     fn poke_api_client(&self) -> PokeClient {
         self.poke_api.clone().into()
     }
@@ -46,21 +44,42 @@ impl Settings {
     fn translation_api_client(&self) -> TranslationClient {
         self.translation_api.clone().into()
     }
-}
 
-fn parse(input: String) -> Result<Duration, String> {
-    input
-        .parse::<humantime::Duration>()
-        .map(Into::into)
-        .map_err(|e| format!("{}", e))
-}
+    fn translation_rules(&self) -> Rules {
+        self.translation_api.rules.clone().into()
+    }
 
-fn env_var(name: &'static str) -> String {
-    std::env::var(name).unwrap_or_else(|_| panic!("{} not present", name))
+    fn translation_queue(&self) -> Option<TranslationQueue> {
+        self.translation_api.queue.clone().map(Into::into)
+    }
 }
 
 #[rocket::main]
 async fn main() {
-    let settings = Settings::from_env();
-    let _ = rocket(settings).launch().await;
+    let cli = Cli::parse();
+    let settings = cli.args.settings().unwrap_or_else(|error| exit_with(error));
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            let rocket = rocket(settings.clone());
+            if let Some(clients) = rocket.state::<Reloadable<Clients>>() {
+                tokio::spawn(reload::watch(cli.args, settings, clients.clone()));
+            }
+            let _ = rocket.launch().await;
+        }
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => println!("The configuration is valid"),
+        Command::Query(query) => {
+            match cli::run(query, cli.format, &Clients::from(&settings)).await {
+                Ok(output) => println!("{}", output),
+                Err(error) => exit_with(error),
+            }
+        }
+    }
+}
+
+fn exit_with(error: impl Display) -> ! {
+    eprintln!("{}", error);
+    std::process::exit(1);
 }
@@ -1,7 +1,12 @@
+use crate::cache::CacheSettings;
+use crate::libretranslate::LibreTranslateSettings;
+use crate::queue::QueueSettings;
 use crate::rocket;
+use crate::rules::default_rules;
+use crate::server::ServerSettings;
 use crate::translation::Language;
 use crate::{
-    pokeapi::{PokeApiSettings, PokeClient},
+    pokeapi::{default_languages, PokeApiSettings, PokeClient, VersionStrategy},
     translation::{TranslationClient, TranslationSettings},
     Settings,
 };
@@ -9,19 +14,39 @@ use crate::{
 use rocket::local::asynchronous::Client;
 use std::time::Duration;
 use wiremock::{
-    matchers::{any, method, path},
+    matchers::{any, body_string_contains, header, method, path},
     Mock, MockServer, ResponseTemplate,
 };
 
 pub const RAW_MEWTWO: &'static str = include_str!("../fixtures/pokeapi/mewtwo.json");
 pub const RAW_DIGLETT: &'static str = include_str!("../fixtures/pokeapi/diglett.json");
 pub const RAW_DITTO: &'static str = include_str!("../fixtures/pokeapi/ditto.json");
+pub const RAW_DITTO_IN_JAPANESE: &'static str =
+    include_str!("../fixtures/pokeapi/ditto_in_japanese.json");
 pub const RAW_BULBASAUR: &'static str = include_str!("../fixtures/pokeapi/bulbasaur.json");
+pub const RAW_MEWTWO_POKEMON: &'static str =
+    include_str!("../fixtures/pokeapi/mewtwo_pokemon.json");
+pub const RAW_LUCARIO: &'static str = include_str!("../fixtures/pokeapi/lucario.json");
+pub const RAW_DEOXYS: &'static str = include_str!("../fixtures/pokeapi/deoxys.json");
+pub const RAW_DEOXYS_NORMAL_POKEMON: &'static str =
+    include_str!("../fixtures/pokeapi/deoxys_normal_pokemon.json");
+pub const RAW_SPECIES_INDEX: &'static str = include_str!("../fixtures/pokeapi/species_index.json");
+pub const RAW_CAVE_HABITAT: &'static str = include_str!("../fixtures/pokeapi/cave_habitat.json");
+pub const RAW_LEGENDARIES: &'static str = include_str!("../fixtures/pokeapi/legendaries.json");
+pub const RAW_LOCALIZED_NAME: &'static str =
+    include_str!("../fixtures/pokeapi/localized_name.json");
+pub const RAW_NO_LOCALIZED_NAME: &'static str =
+    include_str!("../fixtures/pokeapi/no_localized_name.json");
+pub const RAW_KANTO_POKEDEX: &'static str = include_str!("../fixtures/pokeapi/kanto_pokedex.json");
+pub const RAW_BULBASAUR_EVOLUTION_CHAIN: &'static str =
+    include_str!("../fixtures/pokeapi/bulbasaur_evolution_chain.json");
 
 pub const DIGLETT_AS_YODA: &'static str = include_str!("../fixtures/translation/diglett_yoda.json");
 pub const MEWTWO_AS_YODA: &'static str = include_str!("../fixtures/translation/mewtwo_yoda.json");
 pub const BULBASAUR_AS_SHAKESPEARE: &'static str =
     include_str!("../fixtures/translation/bulbasaur_shakespeare.json");
+pub const BULBASAUR_IN_GERMAN: &'static str =
+    include_str!("../fixtures/translation/bulbasaur_german.json");
 
 const CONNECTION_TIMEOUT: Duration = Duration::from_millis(100);
 
@@ -30,6 +55,10 @@ pub async fn setup_poke_api() -> MockPokeApi {
     let poke_api_settings = PokeApiSettings {
         base_url: format!("http://{}", server.address().to_string()),
         timeout: CONNECTION_TIMEOUT,
+        languages: default_languages(),
+        version_strategy: VersionStrategy::default(),
+        cache: CacheSettings::default(),
+        graphql_url: None,
     };
 
     MockPokeApi {
@@ -44,6 +73,17 @@ pub async fn setup_translation_api() -> MockTranslationApi {
     let translation_api_settings = TranslationSettings {
         base_url: format!("http://{}", server.address().to_string()),
         timeout: CONNECTION_TIMEOUT,
+        cache_file: None,
+        rules: default_rules(),
+        // Both APIs are served by the same mock, as their paths don't overlap
+        libre_translate: Some(LibreTranslateSettings {
+            base_url: format!("http://{}", server.address().to_string()),
+            timeout: CONNECTION_TIMEOUT,
+            api_key: None,
+        }),
+        queue: None,
+        api_secret: None,
+        api_secret_file: None,
     };
 
     MockTranslationApi {
@@ -54,12 +94,23 @@ pub async fn setup_translation_api() -> MockTranslationApi {
 }
 
 pub async fn setup() -> (Client, MockPokeApi, MockTranslationApi) {
+    setup_with_queue(None).await
+}
+
+/// Like `setup`, but translating in the background
+pub async fn setup_with_queue(
+    queue: Option<QueueSettings>,
+) -> (Client, MockPokeApi, MockTranslationApi) {
     let mock_poke_api = setup_poke_api().await;
     let mock_translation_api = setup_translation_api().await;
 
     let settings = Settings {
+        server: ServerSettings::default(),
         poke_api: mock_poke_api.settings.clone(),
-        translation_api: mock_translation_api.settings.clone(),
+        translation_api: TranslationSettings {
+            queue,
+            ..mock_translation_api.settings.clone()
+        },
     };
 
     let client = Client::tracked(rocket(settings)).await.unwrap();
@@ -75,9 +126,120 @@ pub struct MockPokeApi {
 
 impl MockPokeApi {
     pub async fn is_present(&self, pokemon: &'static str, response: &'static str) {
+        self.is_present_times(pokemon, response, 1).await;
+    }
+
+    pub async fn is_present_times(
+        &self,
+        pokemon: &'static str,
+        response: &'static str,
+        times: u64,
+    ) {
         let mock = Mock::given(method("GET"))
             .and(path(format!("/api/v2/pokemon-species/{}", pokemon)))
             .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(times);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_battle_data(&self, pokemon: &'static str, response: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path(format!("/api/v2/pokemon/{}", pokemon)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_broken_battle_data(&self, pokemon: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path(format!("/api/v2/pokemon/{}", pokemon)))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_species_index(&self, response: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path("/api/v2/pokemon-species"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_broken_species_index(&self) {
+        let mock = Mock::given(method("GET"))
+            .and(path("/api/v2/pokemon-species"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_habitat(&self, habitat: &'static str, response: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path(format!("/api/v2/pokemon-habitat/{}", habitat)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_legendaries(&self, response: &'static str) {
+        let mock = Mock::given(method("POST"))
+            .and(path("/graphql/v1beta"))
+            .and(body_string_contains("is_legendary"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_localized_name(&self, name: &'static str, response: &'static str) {
+        self.has_localized_name_times(name, response, 1).await;
+    }
+
+    pub async fn has_localized_name_times(
+        &self,
+        name: &'static str,
+        response: &'static str,
+        times: u64,
+    ) {
+        let mock = Mock::given(method("POST"))
+            .and(path("/graphql/v1beta"))
+            .and(body_string_contains("pokemon_v2_pokemonspeciesname"))
+            .and(body_string_contains(format!(r#""name":"{}""#, name)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(times);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_pokedex(&self, pokedex: &'static str, response: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path(format!("/api/v2/pokedex/{}", pokedex)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn has_evolution_chain(&self, id: u32, response: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path(format!("/api/v2/evolution-chain/{}/", id)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub async fn is_missing(&self, pokemon: &'static str) {
+        let mock = Mock::given(method("GET"))
+            .and(path(format!("/api/v2/pokemon-species/{}", pokemon)))
+            .respond_with(ResponseTemplate::new(404))
             .expect(1);
 
         self.server.register(mock).await;
@@ -97,9 +259,10 @@ impl MockPokeApi {
     }
 
     pub async fn no_pokemon_exist(&self) {
+        // Looking up a Pokemon asks for both its species and its battle data
         let mock = Mock::given(any())
             .respond_with(ResponseTemplate::new(404))
-            .expect(1);
+            .expect(1..);
 
         self.server.register(mock).await;
     }
@@ -107,6 +270,10 @@ impl MockPokeApi {
     pub(crate) fn client(&self) -> &PokeClient {
         &self.client
     }
+
+    pub(crate) fn settings(&self) -> &PokeApiSettings {
+        &self.settings
+    }
 }
 
 pub struct MockTranslationApi {
@@ -125,6 +292,56 @@ impl MockTranslationApi {
         self.server.register(mock).await;
     }
 
+    pub(crate) async fn can_translate_with_quota(
+        &self,
+        lang: Language,
+        response: &'static str,
+        remaining: u32,
+        reset: &'static str,
+    ) {
+        let mock = Mock::given(method("POST"))
+            .and(path(format!("/translate/{}", lang)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("X-RateLimit-Limit", "5")
+                    .insert_header("X-RateLimit-Remaining", remaining.to_string().as_str())
+                    .insert_header("X-RateLimit-Reset", reset)
+                    .set_body_raw(response, "application/json"),
+            )
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub(crate) async fn can_translate_naturally(
+        &self,
+        target: &'static str,
+        response: &'static str,
+    ) {
+        let mock = Mock::given(method("POST"))
+            .and(path("/translate"))
+            .and(body_string_contains(format!(r#""target":"{}""#, target)))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
+    pub(crate) async fn can_translate_with_secret(
+        &self,
+        secret: &'static str,
+        lang: Language,
+        response: &'static str,
+    ) {
+        let mock = Mock::given(method("POST"))
+            .and(path(format!("/translate/{}", lang)))
+            .and(header("X-Funtranslations-Api-Secret", secret))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+            .expect(1);
+
+        self.server.register(mock).await;
+    }
+
     pub(crate) async fn has_hit_rate_limit(&self) {
         let mock = Mock::given(method("POST"))
             .and(path("/translate/yoda"))
@@ -154,4 +371,8 @@ impl MockTranslationApi {
     pub(crate) fn client(&self) -> &TranslationClient {
         &self.client
     }
+
+    pub(crate) fn settings(&self) -> &TranslationSettings {
+        &self.settings
+    }
 }
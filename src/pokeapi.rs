@@ -1,105 +1,545 @@
-use reqwest::{Client, StatusCode};
-use serde::de::{IgnoredAny, SeqAccess, Visitor};
-use serde::Deserialize;
+use rand::seq::SliceRandom;
+use reqwest::{Client, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
-use crate::server::Pokemon;
+use crate::cache::{Cache, CacheSettings, CacheStats, Cached, Lazy};
+use crate::server::{
+    Ability, Battle, Description, Descriptions, Details, Evolution, EvolutionChain,
+    EvolutionTrigger, Pokemon, Stat,
+};
 
 const FORM_FEED: char = '\u{c}';
 
+/// Large enough to get every species from PokeAPI in a single page
+const SPECIES_INDEX_LIMIT: u32 = 10_000;
+const MAX_SUGGESTIONS: usize = 3;
+
+const LEGENDARIES_QUERY: &str = "query legendaries { legendaries: pokemon_v2_pokemonspecies(where: {is_legendary: {_eq: true}}) { name } }";
+const LOCALIZED_NAME_QUERY: &str = "query localized_name($name: String!) { names: pokemon_v2_pokemonspeciesname(where: {name: {_ilike: $name}}, limit: 1) { species: pokemon_v2_pokemonspecies { name } } }";
+
+/// The game versions in the order they were released, used to find the earliest or latest
+/// flavour text. Versions PokeAPI adds later are considered newer than all of these.
+const RELEASE_ORDER: &[&str] = &[
+    "red",
+    "blue",
+    "yellow",
+    "gold",
+    "silver",
+    "crystal",
+    "ruby",
+    "sapphire",
+    "emerald",
+    "firered",
+    "leafgreen",
+    "diamond",
+    "pearl",
+    "platinum",
+    "heartgold",
+    "soulsilver",
+    "black",
+    "white",
+    "black-2",
+    "white-2",
+    "x",
+    "y",
+    "omega-ruby",
+    "alpha-sapphire",
+    "sun",
+    "moon",
+    "ultra-sun",
+    "ultra-moon",
+    "lets-go-pikachu",
+    "lets-go-eevee",
+    "sword",
+    "shield",
+    "the-isle-of-armor",
+    "the-crown-tundra",
+    "brilliant-diamond",
+    "shining-pearl",
+    "legends-arceus",
+    "scarlet",
+    "violet",
+];
+
+fn release_index(version: &str) -> usize {
+    RELEASE_ORDER
+        .iter()
+        .position(|released| *released == version)
+        .unwrap_or(RELEASE_ORDER.len())
+}
+
 #[derive(Deserialize, Debug)]
 struct Habitat {
     name: String,
 }
 
-fn deserialize_flavour_text<'de, D>(deserializer: D) -> Result<String, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    struct FlavourTextVisitor;
+/// PokeAPI links to most other resources by name and URL, but we only need the name.
+#[derive(Deserialize, Debug)]
+struct NamedResource {
+    name: String,
+}
 
-    impl<'de> Visitor<'de> for FlavourTextVisitor {
-        type Value = String;
+#[derive(Deserialize, Debug)]
+struct FlavourText {
+    flavor_text: String,
+    language: NamedResource,
+    version: NamedResource,
+}
 
-        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-            formatter.write_str("expected an array")
-        }
+#[derive(Deserialize, Debug)]
+struct Genus {
+    genus: String,
+    language: NamedResource,
+}
 
-        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-        where
-            A: SeqAccess<'de>,
-        {
-            #[derive(Deserialize)]
-            struct Language {
-                name: String,
-            }
+#[derive(Deserialize, Debug)]
+struct ExternalPokemon {
+    id: u32,
+    name: String,
+    is_legendary: bool,
+    is_mythical: bool,
+    is_baby: bool,
+    /// Species from generation IV on don't have one
+    habitat: Option<Habitat>,
+    flavor_text_entries: Vec<FlavourText>,
+    #[serde(default)]
+    genera: Vec<Genus>,
+    color: NamedResource,
+    shape: Option<NamedResource>,
+    capture_rate: u8,
+    base_happiness: Option<u8>,
+    gender_rate: i8,
+    hatch_counter: Option<u8>,
+    growth_rate: NamedResource,
+    #[serde(default)]
+    egg_groups: Vec<NamedResource>,
+    generation: NamedResource,
+    evolution_chain: Option<Link>,
+    evolves_from_species: Option<NamedResource>,
+    #[serde(default)]
+    varieties: Vec<Variety>,
+    /// What the species is called in the languages PokeAPI knows
+    #[serde(default)]
+    names: Vec<Named>,
+}
 
-            #[derive(Deserialize)]
-            struct FlavorText {
-                flavor_text: String,
-                language: Language,
-            }
+/// One of the pokemon of a species, such as mewtwo-mega-x
+#[derive(Deserialize, Debug)]
+struct Variety {
+    is_default: bool,
+    pokemon: NamedResource,
+}
 
-            let mut found = None;
-            while let Some(flavour_entry) = seq.next_element::<FlavorText>()? {
-                if flavour_entry.language.name == "en" {
-                    found = Some(flavour_entry.flavor_text);
-                    break;
-                }
-            }
-            // Consume any remaining flavour text entries without actually allocating any memory.
-            // Serde requires that we consume the entire available JSON, probably to know where to
-            // continue deserializing other fields?
-            while let Some(_ignored_any) = seq.next_element::<IgnoredAny>()? {}
+/// The parts of `/api/v2/pokemon/<name>` that matter in battle
+#[derive(Deserialize, Debug)]
+struct ExternalBattleData {
+    types: Vec<TypeSlot>,
+    stats: Vec<BaseStat>,
+    abilities: Vec<AbilitySlot>,
+    height: u32,
+    weight: u32,
+}
 
-            found.ok_or_else(|| {
-                serde::de::Error::custom("did not find an english variant of the flavour text")
-            })
+#[derive(Deserialize, Debug)]
+struct TypeSlot {
+    #[serde(rename = "type")]
+    kind: NamedResource,
+}
+
+#[derive(Deserialize, Debug)]
+struct BaseStat {
+    base_stat: u8,
+    stat: NamedResource,
+}
+
+#[derive(Deserialize, Debug)]
+struct AbilitySlot {
+    ability: NamedResource,
+    is_hidden: bool,
+}
+
+impl From<ExternalBattleData> for Battle {
+    fn from(data: ExternalBattleData) -> Self {
+        Battle {
+            types: data.types.into_iter().map(|slot| slot.kind.name).collect(),
+            stats: data
+                .stats
+                .into_iter()
+                .map(|stat| Stat {
+                    name: stat.stat.name,
+                    base: stat.base_stat,
+                })
+                .collect(),
+            abilities: data
+                .abilities
+                .into_iter()
+                .map(|slot| Ability {
+                    name: slot.ability.name,
+                    is_hidden: slot.is_hidden,
+                })
+                .collect(),
+            height: data.height,
+            weight: data.weight,
         }
     }
+}
 
-    deserializer.deserialize_seq(FlavourTextVisitor)
+#[derive(Deserialize, Debug)]
+struct SpeciesIndex {
+    results: Vec<NamedResource>,
 }
 
+/// A habitat with all the species living in it, rather than just its name
 #[derive(Deserialize, Debug)]
-struct ExternalPokemon {
+struct PokemonHabitat {
+    pokemon_species: Vec<NamedResource>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQl<T> {
+    data: T,
+}
+
+#[derive(Deserialize, Debug)]
+struct Legendaries {
+    legendaries: Vec<Named>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Named {
     name: String,
-    is_legendary: bool,
-    habitat: Habitat,
-    #[serde(
-        rename = "flavor_text_entries",
-        deserialize_with = "deserialize_flavour_text"
-    )]
-    description: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LocalizedNames {
+    names: Vec<LocalizedName>,
+}
+
+/// The species a name in one of the languages PokeAPI knows belongs to
+#[derive(Deserialize, Debug)]
+struct LocalizedName {
+    species: Named,
+}
+
+#[derive(Deserialize, Debug)]
+struct Pokedex {
+    pokemon_entries: Vec<PokedexEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PokedexEntry {
+    entry_number: u32,
+    pokemon_species: NamedResource,
+}
+
+#[derive(Deserialize, Debug)]
+struct Link {
+    url: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExternalEvolutionChain {
+    chain: ChainLink,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChainLink {
+    species: NamedResource,
+    is_baby: bool,
+    evolution_details: Vec<EvolutionDetail>,
+    evolves_to: Vec<ChainLink>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EvolutionDetail {
+    trigger: NamedResource,
+    min_level: Option<u8>,
+    item: Option<NamedResource>,
+    held_item: Option<NamedResource>,
+    min_happiness: Option<u8>,
+    min_affection: Option<u8>,
+    #[serde(default)]
+    time_of_day: String,
+    known_move: Option<NamedResource>,
+    location: Option<NamedResource>,
+}
+
+impl From<ChainLink> for Evolution {
+    fn from(link: ChainLink) -> Self {
+        Evolution {
+            name: link.species.name,
+            is_baby: link.is_baby,
+            triggers: link
+                .evolution_details
+                .into_iter()
+                .map(EvolutionTrigger::from)
+                .collect(),
+            evolves_to: link.evolves_to.into_iter().map(Evolution::from).collect(),
+        }
+    }
+}
+
+impl From<EvolutionDetail> for EvolutionTrigger {
+    fn from(detail: EvolutionDetail) -> Self {
+        let name = |resource: NamedResource| resource.name;
+
+        EvolutionTrigger {
+            trigger: detail.trigger.name,
+            min_level: detail.min_level,
+            item: detail.item.map(name),
+            held_item: detail.held_item.map(name),
+            min_happiness: detail.min_happiness,
+            min_affection: detail.min_affection,
+            // PokeAPI uses an empty string when the time of day does not matter
+            time_of_day: Some(detail.time_of_day).filter(|time| !time.is_empty()),
+            known_move: detail.known_move.map(name),
+            location: detail.location.map(name),
+        }
+    }
+}
+
+impl ExternalPokemon {
+    /// Walks the chain of languages in order and picks a flavour text from the first one that
+    /// has any. A regional tag such as `de-CH` also matches its primary language `de`.
+    /// Within that language the requested version wins, otherwise the strategy decides.
+    fn flavour_text<'a, I>(
+        &self,
+        languages: I,
+        version: Option<&str>,
+        strategy: VersionStrategy,
+    ) -> Option<&FlavourText>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        languages
+            .into_iter()
+            .flat_map(|tag| candidates(tag))
+            .find_map(|candidate| {
+                let entries = self
+                    .flavor_text_entries
+                    .iter()
+                    .filter(|entry| entry.language.name.eq_ignore_ascii_case(candidate))
+                    .collect::<Vec<_>>();
+
+                version
+                    .and_then(|version| {
+                        entries
+                            .iter()
+                            .find(|entry| entry.version.name.eq_ignore_ascii_case(version))
+                            .copied()
+                    })
+                    .or_else(|| strategy.pick(&entries))
+            })
+    }
+
+    /// The pokemon PokeAPI keeps the battle data of the species under. Usually named after the
+    /// species, but not always, such as deoxys-normal.
+    fn default_pokemon(&self) -> Option<&str> {
+        self.varieties
+            .iter()
+            .find(|variety| variety.is_default)
+            .map(|variety| variety.pokemon.name.as_str())
+    }
+
+    fn details(&self, language: &str) -> Details {
+        let genus = self
+            .genera
+            .iter()
+            .find(|genus| genus.language.name == language)
+            .map(|genus| genus.genus.clone());
+
+        Details {
+            genus,
+            color: self.color.name.clone(),
+            shape: self.shape.as_ref().map(|shape| shape.name.clone()),
+            capture_rate: self.capture_rate,
+            base_happiness: self.base_happiness,
+            gender_rate: self.gender_rate,
+            hatch_counter: self.hatch_counter,
+            growth_rate: self.growth_rate.name.clone(),
+            egg_groups: self
+                .egg_groups
+                .iter()
+                .map(|group| group.name.clone())
+                .collect(),
+            generation: self.generation.name.clone(),
+            is_mythical: self.is_mythical,
+            is_baby: self.is_baby,
+        }
+    }
+}
+
+fn candidates(tag: &str) -> impl Iterator<Item = &str> {
+    let primary = tag.split('-').next().filter(|primary| *primary != tag);
+    std::iter::once(tag).chain(primary)
+}
+
+/// The number of single-character edits needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// PokeAPI looks up species by name or by their national Pokédex number.
+/// Users like to write the latter as "#025", which we read as plain 25.
+fn national_number(name: &str) -> Option<u32> {
+    let name = name.trim();
+    name.strip_prefix('#').unwrap_or(name).parse::<u32>().ok()
+}
+
+/// The key under which PokeAPI would know the Pokemon the user asked for, if they used its
+/// English name or its number
+fn key(name: &str) -> String {
+    match national_number(name) {
+        Some(number) => number.to_string(),
+        None => normalize_name(name),
+    }
+}
+
+/// Matches `name` literally in a GraphQL `_ilike` filter
+fn escape_like(name: &str) -> String {
+    name.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Turns a name as people write it, such as "Mr. Mime" or "Nidoran ♀", into the slug PokeAPI
+/// uses for it: "mr-mime" and "nidoran-f".
+fn normalize_name(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    let separate = |slug: &mut String| {
+        if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    };
+
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        match c {
+            '♀' => {
+                separate(&mut slug);
+                slug.push('f');
+            }
+            '♂' => {
+                separate(&mut slug);
+                slug.push('m');
+            }
+            'é' | 'è' | 'ê' => slug.push('e'),
+            '\'' | '’' | '.' | '#' => {}
+            c if c.is_whitespace() || c == '-' || c == '_' || c == ':' => separate(&mut slug),
+            c => slug.push(c),
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
 }
 
 fn clean_text(input: &str) -> String {
     input.replace(&['\n', FORM_FEED][..], " ")
 }
 
-impl From<ExternalPokemon> for Pokemon {
-    fn from(api_pokemon: ExternalPokemon) -> Self {
-        let description = clean_text(&api_pokemon.description);
+/// How to choose between the flavour texts of the different game versions.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum VersionStrategy {
+    /// Whichever comes first in the PokeAPI response
+    #[default]
+    First,
+    Earliest,
+    Latest,
+    Random,
+}
 
-        Pokemon {
-            name: api_pokemon.name,
-            description,
-            habitat: api_pokemon.habitat.name,
-            is_legendary: api_pokemon.is_legendary,
+impl FromStr for VersionStrategy {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "first" => Ok(VersionStrategy::First),
+            "earliest" => Ok(VersionStrategy::Earliest),
+            "latest" => Ok(VersionStrategy::Latest),
+            "random" => Ok(VersionStrategy::Random),
+            other => Err(format!("'{}' is not a known version strategy", other)),
         }
     }
 }
 
+impl VersionStrategy {
+    fn pick<'a>(&self, entries: &[&'a FlavourText]) -> Option<&'a FlavourText> {
+        let by_release = |entry: &&&FlavourText| release_index(&entry.version.name);
+
+        match self {
+            VersionStrategy::First => entries.first(),
+            VersionStrategy::Earliest => entries.iter().min_by_key(by_release),
+            VersionStrategy::Latest => entries.iter().max_by_key(by_release),
+            VersionStrategy::Random => entries.choose(&mut rand::thread_rng()),
+        }
+        .copied()
+    }
+}
+
+/// What the caller would like to see in the description of a Pokemon.
+/// The requested languages are tried first, before the fallback chain of the `PokeClient`.
+/// Without a `strategy` the one configured for the `PokeClient` is used.
+#[derive(Debug, Default)]
+pub(crate) struct Preferences {
+    pub(crate) languages: Vec<String>,
+    pub(crate) version: Option<String>,
+    pub(crate) strategy: Option<VersionStrategy>,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub(crate) struct PokeApiSettings {
     pub(crate) base_url: String,
+    #[serde(with = "humantime_serde")]
     pub(crate) timeout: Duration,
+    #[serde(default = "default_languages")]
+    pub(crate) languages: Vec<String>,
+    #[serde(default)]
+    pub(crate) version_strategy: VersionStrategy,
+    #[serde(default)]
+    pub(crate) cache: CacheSettings,
+    /// For what the REST API can't filter by: the legendary Pokemon and names in other
+    /// languages. `<base_url>/graphql/v1beta` if absent, as in a self-hosted PokeAPI.
+    #[serde(default)]
+    pub(crate) graphql_url: Option<String>,
+}
+
+pub(crate) fn default_languages() -> Vec<String> {
+    vec!["en".into()]
 }
 
 impl From<PokeApiSettings> for PokeClient {
     fn from(settings: PokeApiSettings) -> Self {
-        PokeClient::new(settings.base_url, settings.timeout)
+        let graphql_url = match settings.graphql_url {
+            Some(graphql_url) => graphql_url,
+            None => format!("{}/graphql/v1beta", settings.base_url),
+        };
+        PokeClient::new(
+            settings.base_url,
+            settings.timeout,
+            settings.languages,
+            settings.version_strategy,
+            settings.cache,
+            graphql_url,
+        )
     }
 }
 
@@ -107,14 +547,28 @@ impl From<PokeApiSettings> for PokeClient {
 pub(crate) struct PokeClient {
     client: Client,
     domain: String,
+    graphql_url: String,
+    languages: Vec<String>,
+    version_strategy: VersionStrategy,
+    /// The names of all species, only loaded once someone misspells a name
+    species_index: Arc<Lazy<Vec<String>>>,
+    /// Names in other languages, such as "bisasam", mapped to the species they belong to.
+    /// Learned from the species we fetched, or asked for when PokeAPI doesn't know a name.
+    localized_names: Arc<Cache<String>>,
+    species_cache: Arc<Cache<Arc<ExternalPokemon>>>,
+    battle_cache: Arc<Cache<Battle>>,
 }
 
 #[derive(Error, Debug)]
 pub(crate) enum Error {
     #[error("Did not find pokemon")]
     NoSuchPokemon,
+    #[error("Did not find the habitat '{0}'")]
+    NoSuchHabitat(String),
     #[error("Received bad JSON from the server")]
     BadJson,
+    #[error("No flavour text in any of the languages: {0}")]
+    NoDescription(String),
     #[error("Failed to establish connection")]
     Other(reqwest::Error),
 }
@@ -132,34 +586,433 @@ impl From<reqwest::Error> for Error {
 }
 
 impl PokeClient {
-    pub(crate) fn new(domain: String, timeout: Duration) -> PokeClient {
+    pub(crate) fn new(
+        domain: String,
+        timeout: Duration,
+        languages: Vec<String>,
+        version_strategy: VersionStrategy,
+        cache: CacheSettings,
+        graphql_url: String,
+    ) -> PokeClient {
         let client = Client::builder()
             .timeout(timeout)
             .build()
             .expect("failed to construct a viable PokeApi client");
-        PokeClient { client, domain }
+        PokeClient {
+            client,
+            domain,
+            graphql_url,
+            languages,
+            version_strategy,
+            // Retried as soon as PokeAPI would be asked again about a missing name
+            species_index: Arc::new(Lazy::new(cache.negative_ttl)),
+            localized_names: Arc::new(Cache::new(cache.clone())),
+            species_cache: Arc::new(Cache::new(cache.clone())),
+            battle_cache: Arc::new(Cache::new(cache)),
+        }
+    }
+
+    /// A client for `settings` that keeps what this one already looked up: the caches unless
+    /// PokeAPI or the cache settings changed, and the localized names unless the GraphQL
+    /// endpoint changed too
+    pub(crate) fn reconfigured(&self, settings: PokeApiSettings) -> PokeClient {
+        let mut client = PokeClient::from(settings);
+        let same_domain = client.domain == self.domain;
+        let same_cache =
+            same_domain && client.species_cache.settings() == self.species_cache.settings();
+
+        if same_domain {
+            client.species_index = self.species_index.clone();
+        }
+        if same_cache {
+            client.species_cache = self.species_cache.clone();
+            client.battle_cache = self.battle_cache.clone();
+        }
+        if same_cache && client.graphql_url == self.graphql_url {
+            client.localized_names = self.localized_names.clone();
+        }
+        client
+    }
+
+    pub(crate) fn species_cache_stats(&self) -> CacheStats {
+        self.species_cache.stats()
+    }
+
+    pub(crate) fn battle_cache_stats(&self) -> CacheStats {
+        self.battle_cache.stats()
+    }
+
+    /// The names of the species closest to `name`, to help with typos.
+    /// Failing to load the index of all species only means there are no suggestions.
+    pub(crate) async fn suggestions(&self, name: &str) -> Vec<String> {
+        let index = match self
+            .species_index
+            .get("the index of species", || self.load_species_index())
+            .await
+        {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+
+        let name = normalize_name(name);
+        let mut close = index
+            .iter()
+            .map(|species| (edit_distance(&name, species), species))
+            .filter(|(distance, species)| *distance <= name.len().max(species.len()) / 2)
+            .collect::<Vec<_>>();
+        // Typos tend to happen towards the end of a name, so on a tie we prefer the species
+        // that starts the same way
+        close.sort_by_key(|(distance, species)| {
+            let common_prefix = name
+                .chars()
+                .zip(species.chars())
+                .take_while(|(a, b)| a == b)
+                .count();
+            (*distance, Reverse(common_prefix), species.as_str())
+        });
+
+        close
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, species)| species.clone())
+            .collect()
+    }
+
+    async fn load_species_index(&self) -> Result<Vec<String>, Error> {
+        log::info!("Loading the index of all species");
+
+        let index = self
+            .client
+            .get(format!(
+                "{}/api/v2/pokemon-species?limit={}",
+                self.domain, SPECIES_INDEX_LIMIT
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SpeciesIndex>()
+            .await?;
+
+        Ok(index
+            .results
+            .into_iter()
+            .map(|species| species.name)
+            .collect())
+    }
+
+    pub(crate) async fn find(
+        &self,
+        name: &str,
+        preferences: &Preferences,
+    ) -> Result<Pokemon, Error> {
+        // The species holds the description, the pokemon itself what matters in battle.
+        // The latter is a nice-to-have, so we don't fail if it is missing.
+        let key = key(name);
+        let (api_pokemon, battle) = tokio::join!(self.species(&key, name), self.battle(&key));
+        let api_pokemon = api_pokemon?;
+        let battle = match (battle, api_pokemon.default_pokemon()) {
+            (Err(Error::NoSuchPokemon), Some(pokemon)) if pokemon != key => {
+                self.battle(pokemon).await
+            }
+            (battle, _) => battle,
+        }
+        .map_err(|error| log::warn!("No battle data for '{}': {}", name, error))
+        .ok();
+
+        let languages = preferences.languages.iter().chain(self.languages.iter());
+        let strategy = preferences.strategy.unwrap_or(self.version_strategy);
+        let flavour_text = api_pokemon
+            .flavour_text(languages.clone(), preferences.version.as_deref(), strategy)
+            .ok_or_else(|| {
+                let tried = languages.map(String::as_str).collect::<Vec<_>>();
+                Error::NoDescription(tried.join(", "))
+            })?;
+
+        Ok(Pokemon {
+            id: api_pokemon.id,
+            name: api_pokemon.name.clone(),
+            description: clean_text(&flavour_text.flavor_text),
+            language: flavour_text.language.name.clone(),
+            version: flavour_text.version.name.clone(),
+            habitat: api_pokemon
+                .habitat
+                .as_ref()
+                .map(|habitat| habitat.name.clone()),
+            is_legendary: api_pokemon.is_legendary,
+            details: api_pokemon.details(&flavour_text.language.name),
+            battle,
+        })
     }
 
-    pub(crate) async fn find(&self, name: &str) -> Result<Pokemon, Error> {
-        log::info!("Getting information about {}", name);
+    /// All flavour texts of a Pokemon, where texts repeated across game versions are merged.
+    pub(crate) async fn descriptions(&self, name: &str) -> Result<Descriptions, Error> {
+        let api_pokemon = self.species(&key(name), name).await?;
+
+        let mut descriptions: Vec<Description> = Vec::new();
+        for entry in &api_pokemon.flavor_text_entries {
+            let text = clean_text(&entry.flavor_text);
+            let version = entry.version.name.clone();
+
+            match descriptions
+                .iter_mut()
+                .find(|seen| seen.language == entry.language.name && seen.description == text)
+            {
+                Some(seen) if seen.versions.contains(&version) => {}
+                Some(seen) => seen.versions.push(version),
+                None => descriptions.push(Description {
+                    description: text,
+                    language: entry.language.name.clone(),
+                    versions: vec![version],
+                }),
+            }
+        }
+
+        Ok(Descriptions {
+            name: api_pokemon.name.clone(),
+            descriptions,
+        })
+    }
+
+    /// The evolution chain the Pokemon is part of, starting from its earliest form.
+    pub(crate) async fn evolutions(&self, name: &str) -> Result<EvolutionChain, Error> {
+        let api_pokemon = self.species(&key(name), name).await?;
+
+        let chain = match &api_pokemon.evolution_chain {
+            Some(link) => self.follow::<ExternalEvolutionChain>(link).await?.chain,
+            None => ChainLink {
+                species: NamedResource {
+                    name: api_pokemon.name.clone(),
+                },
+                is_baby: api_pokemon.is_baby,
+                evolution_details: Vec::new(),
+                evolves_to: Vec::new(),
+            },
+        };
+
+        Ok(EvolutionChain {
+            name: api_pokemon.name.clone(),
+            evolves_from: api_pokemon
+                .evolves_from_species
+                .as_ref()
+                .map(|species| species.name.clone()),
+            chain: chain.into(),
+        })
+    }
+
+    /// PokeAPI links to other resources with absolute URLs, so we keep the path but point it at
+    /// our configured domain.
+    async fn follow<T: DeserializeOwned>(&self, link: &Link) -> Result<T, Error> {
+        let path = Url::parse(&link.url)
+            .map(|url| url.path().to_string())
+            .map_err(|_| Error::BadJson)?;
+
+        log::info!("Following link to {}", path);
 
         self.client
-            .get(format!("{}/api/v2/pokemon-species/{}", self.domain, name))
+            .get(format!("{}{}", self.domain, path))
             .send()
             .await?
             .error_for_status()?
-            .json::<ExternalPokemon>()
+            .json::<T>()
             .await
             .map_err(Error::from)
-            .map(Pokemon::from)
     }
+
+    /// Finds the Pokemon listed under `number` in a regional Pokédex such as "kanto".
+    pub(crate) async fn find_in_pokedex(
+        &self,
+        pokedex: &str,
+        number: u32,
+        preferences: &Preferences,
+    ) -> Result<Pokemon, Error> {
+        log::info!("Looking up #{} in the {} Pokédex", number, pokedex);
+
+        let pokedex = self
+            .client
+            .get(format!("{}/api/v2/pokedex/{}", self.domain, pokedex))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Pokedex>()
+            .await?;
+
+        let entry = pokedex
+            .pokemon_entries
+            .into_iter()
+            .find(|entry| entry.entry_number == number)
+            .ok_or(Error::NoSuchPokemon)?;
+
+        self.find(&entry.pokemon_species.name, preferences).await
+    }
+
+    /// The names of all species that live in a habitat such as "cave", in alphabetical order.
+    pub(crate) async fn habitat(&self, habitat: &str) -> Result<Vec<String>, Error> {
+        log::info!("Listing the species living in the {} habitat", habitat);
+
+        let response = self
+            .client
+            .get(format!(
+                "{}/api/v2/pokemon-habitat/{}",
+                self.domain, habitat
+            ))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NoSuchHabitat(habitat.to_string()));
+        }
+        let habitat = response
+            .error_for_status()?
+            .json::<PokemonHabitat>()
+            .await?;
+
+        let mut names = habitat
+            .pokemon_species
+            .into_iter()
+            .map(|species| species.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    /// The names of all legendary species, in alphabetical order.
+    pub(crate) async fn legendaries(&self) -> Result<Vec<String>, Error> {
+        log::info!("Listing the legendary species");
+
+        let mut names = self
+            .graphql::<Legendaries>(LEGENDARIES_QUERY, serde_json::Value::Null)
+            .await?
+            .legendaries
+            .into_iter()
+            .map(|species| species.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn graphql<T: DeserializeOwned>(
+        &self,
+        query: &'static str,
+        variables: serde_json::Value,
+    ) -> Result<T, Error> {
+        #[derive(Serialize)]
+        struct Query {
+            query: &'static str,
+            #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+            variables: serde_json::Value,
+        }
+
+        let response = self
+            .client
+            .post(&self.graphql_url)
+            .json(&Query { query, variables })
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<GraphQl<T>>()
+            .await?;
+
+        Ok(response.data)
+    }
+
+    async fn battle(&self, key: &str) -> Result<Battle, Error> {
+        let fetch = async {
+            self.client
+                .get(format!("{}/api/v2/pokemon/{}", self.domain, key))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ExternalBattleData>()
+                .await
+                .map_err(Error::from)
+                .map(Battle::from)
+        };
+
+        cached(&self.battle_cache, key, fetch).await
+    }
+
+    /// The species the user asked for as `name`, by its `key` or else by what it is called in
+    /// another language, such as "Bisasam"
+    async fn species(&self, key: &str, name: &str) -> Result<Arc<ExternalPokemon>, Error> {
+        match self.species_by_key(key).await {
+            Err(Error::NoSuchPokemon) if national_number(name).is_none() => {
+                let localized = normalize_name(name);
+                let key =
+                    cached(&self.localized_names, &localized, self.species_named(name)).await?;
+                self.species_by_key(&key).await
+            }
+            species => species,
+        }
+    }
+
+    async fn species_by_key(&self, key: &str) -> Result<Arc<ExternalPokemon>, Error> {
+        let fetch = async {
+            log::info!("Getting information about '{}'", key);
+
+            let api_pokemon = self
+                .client
+                .get(format!("{}/api/v2/pokemon-species/{}", self.domain, key))
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ExternalPokemon>()
+                .await?;
+
+            // So its names in other languages lead straight to it from now on
+            for localized in &api_pokemon.names {
+                self.localized_names
+                    .insert(&normalize_name(&localized.name), api_pokemon.name.clone());
+            }
+            Ok(Arc::new(api_pokemon))
+        };
+
+        cached(&self.species_cache, key, fetch).await
+    }
+
+    /// Asks PokeAPI which species is called `name` in any of the languages it knows
+    async fn species_named(&self, name: &str) -> Result<String, Error> {
+        log::info!(
+            "Looking for a species called '{}' in another language",
+            name
+        );
+
+        let variables = serde_json::json!({ "name": escape_like(name.trim()) });
+        self.graphql::<LocalizedNames>(LOCALIZED_NAME_QUERY, variables)
+            .await?
+            .names
+            .into_iter()
+            .next()
+            .map(|localized| localized.species.name)
+            .ok_or(Error::NoSuchPokemon)
+    }
+}
+
+/// Answers from the cache if possible, otherwise remembers what `fetch` found.
+/// Pokemon that don't exist are remembered too, so repeated typos don't all reach PokeAPI.
+async fn cached<V, F>(cache: &Cache<V>, key: &str, fetch: F) -> Result<V, Error>
+where
+    V: Clone,
+    F: Future<Output = Result<V, Error>>,
+{
+    match cache.get(key) {
+        Some(Cached::Found(value)) => return Ok(value),
+        Some(Cached::Missing) => return Err(Error::NoSuchPokemon),
+        None => {}
+    }
+
+    let result = fetch.await;
+    match &result {
+        Ok(value) => cache.insert(key, value.clone()),
+        Err(Error::NoSuchPokemon) => cache.insert_missing(key),
+        Err(_) => {}
+    }
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::mocks;
-    use claim::{assert_err, assert_matches};
+    use claim::assert_matches;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -169,12 +1022,20 @@ mod tests {
 
         assert_eq!(ditto.name, "ditto".to_string());
         assert!(!ditto.is_legendary);
-        assert_eq!(ditto.habitat.name, "urban".to_string());
-        assert_eq!(ditto.description, "It can freely recombine its own cellular structure to\ntransform into other life-forms.".to_string());
+        assert_eq!(
+            ditto.habitat.as_ref().map(|habitat| habitat.name.as_str()),
+            Some("urban")
+        );
+        assert_eq!(
+            ditto
+                .flavour_text(&default_languages(), None, VersionStrategy::First)
+                .map(|entry| entry.flavor_text.as_str()),
+            Some("It can freely recombine its own cellular structure to\ntransform into other life-forms.")
+        );
     }
 
     #[test]
-    fn fails_if_there_is_no_english_flavour_text() {
+    fn falls_back_to_the_next_language_in_the_chain() {
         // Shrunk example with the necessary fields, except the only flavor text is german...
         let german_ditton = r#"
 {
@@ -183,6 +1044,9 @@ mod tests {
       "flavor_text": "Es kann seine Zellstruktur...",
       "language": {
         "name": "de"
+      },
+      "version": {
+        "name": "sword"
       }
     }
   ],
@@ -190,15 +1054,75 @@ mod tests {
     "name": "urban",
     "url": "https://pokeapi.co/api/v2/pokemon-habitat/8/"
   },
+  "id": 132,
   "is_legendary": false,
+  "is_mythical": false,
+  "is_baby": false,
+  "color": { "name": "purple" },
+  "shape": { "name": "ball" },
+  "capture_rate": 35,
+  "base_happiness": 70,
+  "gender_rate": -1,
+  "hatch_counter": 20,
+  "growth_rate": { "name": "medium" },
+  "generation": { "name": "generation-i" },
   "name": "ditto"
 }
             "#;
-        let not_a_ditto = serde_json::from_str::<ExternalPokemon>(german_ditton);
-        assert_err!(not_a_ditto);
+        let german_ditto = serde_json::from_str::<ExternalPokemon>(german_ditton)
+            .expect("unable to deserialize german ditto");
+
+        assert!(german_ditto
+            .flavour_text(&default_languages(), None, VersionStrategy::First)
+            .is_none());
+
+        let chain = vec!["fr".to_string(), "de-CH".to_string(), "en".to_string()];
+        let flavour_text = german_ditto
+            .flavour_text(&chain, None, VersionStrategy::First)
+            .expect("should have found the german flavour text");
+        assert_eq!(flavour_text.language.name, "de".to_string());
     }
 
     #[test]
+    fn picks_the_flavour_text_of_the_requested_version() {
+        let ditto = serde_json::from_str::<ExternalPokemon>(mocks::RAW_DITTO)
+            .expect("unable to deserialize ditto");
+
+        let sword = ditto
+            .flavour_text(&default_languages(), Some("sword"), VersionStrategy::First)
+            .expect("should have found the sword flavour text");
+        assert_eq!(sword.version.name, "sword".to_string());
+
+        // Ditto has no entry for Scarlet, so the strategy decides
+        let unknown = ditto
+            .flavour_text(
+                &default_languages(),
+                Some("scarlet"),
+                VersionStrategy::First,
+            )
+            .expect("should have fallen back to the first flavour text");
+        assert_eq!(unknown.version.name, "y".to_string());
+    }
+
+    #[test]
+    fn picks_the_earliest_or_latest_flavour_text_by_release() {
+        let ditto = serde_json::from_str::<ExternalPokemon>(mocks::RAW_DITTO)
+            .expect("unable to deserialize ditto");
+
+        // The first english entry for Ditto is from Pokemon Y, not from Pokemon Red
+        let earliest = ditto
+            .flavour_text(&default_languages(), None, VersionStrategy::Earliest)
+            .expect("should have found the earliest flavour text");
+        assert_eq!(earliest.version.name, "red".to_string());
+
+        let latest = ditto
+            .flavour_text(&default_languages(), None, VersionStrategy::Latest)
+            .expect("should have found the latest flavour text");
+        assert_eq!(latest.version.name, "shield".to_string());
+    }
+
+    #[test]
+    #[allow(clippy::invisible_characters)]
     fn cleanup_any_line_and_form_feed_characters_from_flavour_text() {
         // Rust can't represent \f in a literal (see fixtures/pokeapi/mewtwo.json) so we use \u{000C}
         // for more examples of the form feed
@@ -217,16 +1141,496 @@ mod tests {
 
         let mewtwo = mock_server
             .client()
-            .find("mewtwo")
+            .find("mewtwo", &Preferences::default())
             .await
             .expect("Failed to get ditto");
 
         assert_eq!(mewtwo.name, "mewtwo".to_string());
-        assert_eq!(mewtwo.habitat, "rare".to_string());
+        assert_eq!(mewtwo.habitat, Some("rare".to_string()));
         assert_eq!(mewtwo.description, "It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.".to_string());
+        assert_eq!(mewtwo.language, "en".to_string());
         assert!(mewtwo.is_legendary);
     }
 
+    #[tokio::test]
+    async fn merges_the_battle_data_of_mewtwo() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("mewtwo", mocks::RAW_MEWTWO).await;
+        mock_server
+            .has_battle_data("mewtwo", mocks::RAW_MEWTWO_POKEMON)
+            .await;
+
+        let mewtwo = mock_server
+            .client()
+            .find("mewtwo", &Preferences::default())
+            .await
+            .expect("Failed to get mewtwo");
+
+        let battle = mewtwo.battle.expect("Missing battle data for mewtwo");
+        assert_eq!(battle.types, vec!["psychic".to_string()]);
+        assert_eq!(battle.stats[3].name, "special-attack".to_string());
+        assert_eq!(battle.stats[3].base, 154);
+        assert_eq!(battle.abilities[1].name, "unnerve".to_string());
+        assert!(battle.abilities[1].is_hidden);
+        assert_eq!(battle.height, 20);
+        assert_eq!(battle.weight, 1220);
+    }
+
+    #[tokio::test]
+    async fn finds_the_battle_data_of_species_with_differently_named_forms() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("deoxys", mocks::RAW_DEOXYS).await;
+        mock_server
+            .has_battle_data("deoxys-normal", mocks::RAW_DEOXYS_NORMAL_POKEMON)
+            .await;
+
+        let deoxys = mock_server
+            .client()
+            .find("deoxys", &Preferences::default())
+            .await
+            .expect("Failed to get deoxys");
+
+        let battle = deoxys.battle.expect("Missing battle data for deoxys");
+        assert_eq!(battle.types, vec!["psychic".to_string()]);
+        assert_eq!(battle.stats[1].base, 150);
+        assert_eq!(battle.weight, 608);
+    }
+
+    #[tokio::test]
+    async fn finds_species_without_a_habitat() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("448", mocks::RAW_LUCARIO).await;
+
+        let lucario = mock_server
+            .client()
+            .find("#448", &Preferences::default())
+            .await
+            .expect("Failed to get lucario");
+
+        assert_eq!(lucario.name, "lucario".to_string());
+        assert_eq!(lucario.habitat, None);
+    }
+
+    #[tokio::test]
+    async fn missing_battle_data_does_not_hide_the_species() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("mewtwo", mocks::RAW_MEWTWO).await;
+        mock_server.has_broken_battle_data("mewtwo").await;
+
+        let mewtwo = mock_server
+            .client()
+            .find("mewtwo", &Preferences::default())
+            .await
+            .expect("Failed to get mewtwo");
+
+        assert_eq!(mewtwo.name, "mewtwo".to_string());
+        assert!(mewtwo.battle.is_none());
+    }
+
+    #[tokio::test]
+    async fn retrieves_mewtwo_in_the_preferred_language() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("mewtwo", mocks::RAW_MEWTWO).await;
+
+        let preferences = Preferences {
+            languages: vec!["de".into()],
+            ..Preferences::default()
+        };
+        let mewtwo = mock_server
+            .client()
+            .find("mewtwo", &preferences)
+            .await
+            .expect("Failed to get mewtwo");
+
+        assert_eq!(mewtwo.language, "de".to_string());
+        assert_eq!(
+            mewtwo.description,
+            "Dieses Pokémon ist das Resultat eines jahrelangen und skrupellosen Experimentes."
+                .to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieves_all_descriptions_of_mewtwo_without_duplicates() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("mewtwo", mocks::RAW_MEWTWO).await;
+
+        let mewtwo = mock_server
+            .client()
+            .descriptions("mewtwo")
+            .await
+            .expect("Failed to get the descriptions of mewtwo");
+
+        assert_eq!(mewtwo.name, "mewtwo".to_string());
+        assert_eq!(mewtwo.descriptions.len(), 66);
+
+        let first = &mewtwo.descriptions[0];
+        assert_eq!(first.description, "It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.".to_string());
+        assert_eq!(first.language, "en".to_string());
+        assert_eq!(first.versions, vec!["red".to_string(), "blue".to_string()]);
+
+        // Let's Go lists the same text three times for the same version
+        let lets_go = mewtwo
+            .descriptions
+            .iter()
+            .find(|description| description.description.starts_with("Psychic power"))
+            .expect("Missing description from Let's Go");
+        assert_eq!(
+            lets_go.versions,
+            vec!["lets-go-pikachu".to_string(), "lets-go-eevee".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn retrieves_the_evolution_chain_of_ivysaur() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        // Shrunk example with the necessary fields to follow the evolution chain
+        let ivysaur = r#"
+{
+  "id": 2,
+  "name": "ivysaur",
+  "is_legendary": false,
+  "is_mythical": false,
+  "is_baby": false,
+  "habitat": { "name": "grassland" },
+  "flavor_text_entries": [],
+  "color": { "name": "green" },
+  "shape": { "name": "quadruped" },
+  "capture_rate": 45,
+  "base_happiness": 50,
+  "gender_rate": 1,
+  "hatch_counter": 20,
+  "growth_rate": { "name": "medium-slow" },
+  "generation": { "name": "generation-i" },
+  "evolution_chain": { "url": "https://pokeapi.co/api/v2/evolution-chain/1/" },
+  "evolves_from_species": { "name": "bulbasaur" }
+}
+            "#;
+        mock_server.is_present("ivysaur", ivysaur).await;
+        mock_server
+            .has_evolution_chain(1, mocks::RAW_BULBASAUR_EVOLUTION_CHAIN)
+            .await;
+
+        let ivysaur = mock_server
+            .client()
+            .evolutions("ivysaur")
+            .await
+            .expect("Failed to get the evolutions of ivysaur");
+
+        assert_eq!(ivysaur.evolves_from, Some("bulbasaur".to_string()));
+        assert_eq!(ivysaur.chain.name, "bulbasaur".to_string());
+        assert!(ivysaur.chain.triggers.is_empty());
+
+        let second_stage = &ivysaur.chain.evolves_to[0];
+        assert_eq!(second_stage.name, "ivysaur".to_string());
+        assert_eq!(second_stage.triggers[0].trigger, "level-up".to_string());
+        assert_eq!(second_stage.triggers[0].min_level, Some(16));
+        assert_eq!(second_stage.evolves_to[0].name, "venusaur".to_string());
+    }
+
+    #[test]
+    fn deserializes_evolutions_triggered_by_items_or_friendship_at_night() {
+        // Shrunk example of Eevee evolving into Vaporeon and Umbreon
+        let eevee = r#"
+{
+  "chain": {
+    "species": { "name": "eevee" },
+    "is_baby": false,
+    "evolution_details": [],
+    "evolves_to": [
+      {
+        "species": { "name": "vaporeon" },
+        "is_baby": false,
+        "evolution_details": [
+          { "trigger": { "name": "use-item" }, "item": { "name": "water-stone" }, "time_of_day": "" }
+        ],
+        "evolves_to": []
+      },
+      {
+        "species": { "name": "umbreon" },
+        "is_baby": false,
+        "evolution_details": [
+          { "trigger": { "name": "level-up" }, "min_happiness": 160, "time_of_day": "night" }
+        ],
+        "evolves_to": []
+      }
+    ]
+  }
+}
+            "#;
+        let chain = serde_json::from_str::<ExternalEvolutionChain>(eevee)
+            .expect("unable to deserialize the evolutions of eevee");
+        let eevee = Evolution::from(chain.chain);
+
+        let vaporeon = &eevee.evolves_to[0].triggers[0];
+        assert_eq!(vaporeon.item, Some("water-stone".to_string()));
+        assert_eq!(vaporeon.time_of_day, None);
+
+        let umbreon = &eevee.evolves_to[1].triggers[0];
+        assert_eq!(umbreon.min_happiness, Some(160));
+        assert_eq!(umbreon.time_of_day, Some("night".to_string()));
+    }
+
+    #[test]
+    fn national_pokedex_numbers_may_start_with_a_hash() {
+        assert_eq!(national_number("#150"), Some(150));
+        assert_eq!(national_number("#025"), Some(25));
+        assert_eq!(national_number("150"), Some(150));
+        assert_eq!(national_number(" 150 "), Some(150));
+        assert_eq!(national_number(" #25"), Some(25));
+        assert_eq!(national_number("mewtwo"), None);
+    }
+
+    #[test]
+    fn normalizes_names_the_way_pokeapi_spells_them() {
+        assert_eq!(normalize_name("Mewtwo"), "mewtwo");
+        assert_eq!(normalize_name(" Mr. Mime "), "mr-mime");
+        assert_eq!(normalize_name("Mime Jr."), "mime-jr");
+        assert_eq!(normalize_name("Farfetch’d"), "farfetchd");
+        assert_eq!(normalize_name("Nidoran ♀"), "nidoran-f");
+        assert_eq!(normalize_name("Type: Null"), "type-null");
+        assert_eq!(normalize_name("Flabébé"), "flabebe");
+        assert_eq!(normalize_name("Ho-Oh"), "ho-oh");
+        assert_eq!(normalize_name("メタモン"), "メタモン");
+    }
+
+    #[tokio::test]
+    async fn asks_for_localized_names_pokeapi_does_not_know() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_missing("bisasam").await;
+        mock_server
+            .has_localized_name("Bisasam", mocks::RAW_LOCALIZED_NAME)
+            .await;
+        // Every name resolves to the same species, so it is only fetched once. Its other
+        // names are in the species, so there is no need to ask for them.
+        mock_server
+            .is_present("bulbasaur", mocks::RAW_BULBASAUR)
+            .await;
+
+        let client = mock_server.client();
+        for localized in &["Bisasam", "フシギダネ", "bulbasaur"] {
+            let bulbasaur = client
+                .find(localized, &Preferences::default())
+                .await
+                .expect("Failed to get bulbasaur by its localized name");
+            assert_eq!(bulbasaur.name, "bulbasaur".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn remembers_names_that_are_unknown_in_every_language() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_missing("pikchu").await;
+        mock_server
+            .has_localized_name("pikchu", mocks::RAW_NO_LOCALIZED_NAME)
+            .await;
+
+        let client = mock_server.client();
+        for _ in 0..2 {
+            let err = client
+                .find("pikchu", &Preferences::default())
+                .await
+                .expect_err("pikchu should not exist");
+            assert_matches!(err, Error::NoSuchPokemon);
+        }
+    }
+
+    #[tokio::test]
+    async fn only_asks_for_localized_names_when_pokeapi_does_not_know_the_name() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("mr-mime", mocks::RAW_DITTO).await;
+        mock_server
+            .has_localized_name_times("Mr. Mime", mocks::RAW_LOCALIZED_NAME, 0)
+            .await;
+
+        let client = mock_server.client();
+        client
+            .find("Mr. Mime", &Preferences::default())
+            .await
+            .expect("Failed to get mr-mime without the localized names");
+    }
+
+    #[tokio::test]
+    async fn only_asks_for_a_pokemon_once_while_it_is_cached() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_present("mewtwo", mocks::RAW_MEWTWO).await;
+        mock_server
+            .has_battle_data("mewtwo", mocks::RAW_MEWTWO_POKEMON)
+            .await;
+
+        let client = mock_server.client();
+        for _ in 0..2 {
+            let mewtwo = client
+                .find("mewtwo", &Preferences::default())
+                .await
+                .expect("Failed to get mewtwo");
+            assert!(mewtwo.battle.is_some());
+        }
+
+        let stats = client.species_cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn remembers_pokemon_that_do_not_exist() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.is_missing("mewthree").await;
+
+        let client = mock_server.client();
+        for _ in 0..2 {
+            let error = client.descriptions("mewthree").await;
+            assert_matches!(error, Err(Error::NoSuchPokemon));
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_mewtwo_in_the_kanto_pokedex() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server
+            .has_pokedex("kanto", mocks::RAW_KANTO_POKEDEX)
+            .await;
+        mock_server.is_present("mewtwo", mocks::RAW_MEWTWO).await;
+
+        let mewtwo = mock_server
+            .client()
+            .find_in_pokedex("kanto", 150, &Preferences::default())
+            .await
+            .expect("Failed to get mewtwo from the kanto pokedex");
+
+        assert_eq!(mewtwo.name, "mewtwo".to_string());
+        assert_eq!(mewtwo.id, 150);
+    }
+
+    #[tokio::test]
+    async fn error_when_the_number_is_not_in_the_pokedex() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server
+            .has_pokedex("kanto", mocks::RAW_KANTO_POKEDEX)
+            .await;
+
+        let err = mock_server
+            .client()
+            .find_in_pokedex("kanto", 151, &Preferences::default())
+            .await
+            .expect_err("should have failed to find #151 in the trimmed kanto pokedex");
+
+        assert_matches!(err, Error::NoSuchPokemon);
+    }
+
+    #[test]
+    fn counts_the_edits_between_two_names() {
+        assert_eq!(edit_distance("pikchu", "pikachu"), 1);
+        assert_eq!(edit_distance("mew2", "mewtwo"), 3);
+        assert_eq!(edit_distance("mewtwo", "mewtwo"), 0);
+        assert_eq!(edit_distance("", "mew"), 3);
+    }
+
+    #[tokio::test]
+    async fn suggests_species_with_similar_names() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server
+            .has_species_index(mocks::RAW_SPECIES_INDEX)
+            .await;
+
+        let client = mock_server.client();
+        assert_eq!(
+            client.suggestions("pikchu").await,
+            vec!["pikachu".to_string(), "raichu".to_string()]
+        );
+        // The index is only loaded once
+        assert_eq!(
+            client.suggestions("mew2").await,
+            vec![
+                "mew".to_string(),
+                "mewtwo".to_string(),
+                "meowth".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_reload_a_broken_species_index_on_every_typo() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.has_broken_species_index().await;
+
+        let client = mock_server.client();
+        assert_eq!(client.suggestions("pikchu").await, Vec::<String>::new());
+        // The mock would fail the test if the index was asked for again
+        assert_eq!(client.suggestions("mew2").await, Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn lists_the_species_living_in_a_habitat() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server
+            .has_habitat("cave", mocks::RAW_CAVE_HABITAT)
+            .await;
+
+        assert_eq!(
+            mock_server
+                .client()
+                .habitat("cave")
+                .await
+                .expect("Unable to list the habitat"),
+            vec![
+                "diglett".to_string(),
+                "onix".to_string(),
+                "zubat".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn error_when_the_habitat_does_not_exist() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        let err = mock_server
+            .client()
+            .habitat("moon")
+            .await
+            .expect_err("There are no Pokemon on the moon");
+
+        assert_matches!(err, Error::NoSuchHabitat(habitat) if habitat == "moon")
+    }
+
+    #[tokio::test]
+    async fn lists_the_legendary_species() {
+        let mock_server = mocks::setup_poke_api().await;
+
+        mock_server.has_legendaries(mocks::RAW_LEGENDARIES).await;
+
+        assert_eq!(
+            mock_server
+                .client()
+                .legendaries()
+                .await
+                .expect("Unable to list the legendaries"),
+            vec![
+                "articuno".to_string(),
+                "mewtwo".to_string(),
+                "moltres".to_string(),
+                "zapdos".to_string()
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn error_when_pokemon_isnt_real() {
         let mock_server = mocks::setup_poke_api().await;
@@ -235,7 +1639,7 @@ mod tests {
 
         let err = mock_server
             .client()
-            .find("not-a-pokemon")
+            .find("not-a-pokemon", &Preferences::default())
             .await
             .expect_err("should have failed to find 'not-a-pokemon'");
 
@@ -250,7 +1654,7 @@ mod tests {
 
         let err = mock_server
             .client()
-            .find("ditto")
+            .find("ditto", &Preferences::default())
             .await
             .expect_err("should have failed with a timeout");
 
@@ -265,10 +1669,10 @@ mod tests {
 
         let err = mock_server
             .client()
-            .find("ditto")
+            .find("ditto", &Preferences::default())
             .await
             .expect_err("should have failed due to bad json");
 
-        assert_matches!(err, Error::BadJson { .. })
+        assert_matches!(err, Error::BadJson)
     }
 }
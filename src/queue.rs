@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::reload::Reloadable;
+use crate::server::Clients;
+use crate::translation::{Error, Language};
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub(crate) struct QueueSettings {
+    /// How long to wait between two translations, e.g. 12 minutes for the 5 an hour of the free tier
+    #[serde(with = "humantime_serde")]
+    pub(crate) interval: Duration,
+    /// Where to keep the queue so it survives restarts, only kept in memory if absent
+    #[serde(default)]
+    pub(crate) file: Option<PathBuf>,
+}
+
+/// A description waiting to be translated
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Job {
+    pub(crate) language: Language,
+    pub(crate) text: String,
+    /// How often it was asked for while waiting, as popular species should be translated first
+    pub(crate) requests: u32,
+}
+
+/// Descriptions to translate in the background, at the pace the translation service allows.
+#[derive(Debug)]
+pub(crate) struct TranslationQueue {
+    jobs: Mutex<Vec<Job>>,
+    added: Notify,
+    /// Tells `persist` to write the jobs to the file
+    changed: Notify,
+    interval: Duration,
+    file: Option<PathBuf>,
+}
+
+impl From<QueueSettings> for TranslationQueue {
+    fn from(settings: QueueSettings) -> Self {
+        let jobs = settings.file.as_deref().map(load).unwrap_or_default();
+        if !jobs.is_empty() {
+            log::info!("{} translations are still waiting", jobs.len());
+        }
+
+        TranslationQueue {
+            jobs: Mutex::new(jobs),
+            added: Notify::new(),
+            changed: Notify::new(),
+            interval: settings.interval,
+            file: settings.file,
+        }
+    }
+}
+
+/// A missing or broken file only means starting with an empty queue
+fn load(path: &Path) -> Vec<Job> {
+    let jobs = match std::fs::read_to_string(path) {
+        Ok(jobs) => jobs,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("Unable to read {}: {}", path.display(), error);
+            return Vec::new();
+        }
+    };
+
+    serde_json::from_str(&jobs).unwrap_or_else(|error| {
+        log::warn!("Ignoring the queue in {}: {}", path.display(), error);
+        Vec::new()
+    })
+}
+
+impl TranslationQueue {
+    /// Queues the text unless it is already waiting, and tells roughly how long it will take.
+    /// That is never sooner than the used up quota, if any, resets.
+    pub(crate) fn push(
+        &self,
+        text: &str,
+        language: Language,
+        exhausted_for: Option<Duration>,
+    ) -> Duration {
+        let mut jobs = self.lock();
+
+        let position = match jobs
+            .iter()
+            .position(|job| job.language == language && job.text == text)
+        {
+            Some(position) => {
+                jobs[position].requests += 1;
+                position
+            }
+            None => {
+                jobs.push(Job {
+                    language,
+                    text: text.to_string(),
+                    requests: 1,
+                });
+                jobs.len() - 1
+            }
+        };
+
+        // The most popular come first, otherwise whatever was waiting longest
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.requests));
+        let position = jobs
+            .iter()
+            .position(|job| job.language == language && job.text == text)
+            .unwrap_or(position);
+
+        self.changed.notify_one();
+        self.added.notify_one();
+        let wait = self.interval * (position as u32 + 1);
+        exhausted_for.map_or(wait, |exhausted_for| wait.max(exhausted_for))
+    }
+
+    fn peek(&self) -> Option<Job> {
+        self.lock().first().cloned()
+    }
+
+    fn remove(&self, done: &Job) {
+        let mut jobs = self.lock();
+        jobs.retain(|job| !(job.language == done.language && job.text == done.text));
+        self.changed.notify_one();
+    }
+
+    /// Writes the jobs as they are now, without holding on to the lock while writing
+    fn save(&self) {
+        if let Some(path) = &self.file {
+            let jobs = serde_json::to_string(&*self.lock()).expect("jobs are valid JSON");
+            if let Err(error) = std::fs::write(path, jobs) {
+                log::warn!("Unable to store the queue in {}: {}", path.display(), error);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Job>> {
+        self.jobs.lock().expect("lock for the queue was poisoned")
+    }
+}
+
+/// Writes the queue to its file after every change, forever. Only this task writes the file,
+/// which keeps the file system out of the requests that add jobs.
+pub(crate) async fn persist(queue: Arc<TranslationQueue>) {
+    if queue.file.is_none() {
+        return;
+    }
+
+    loop {
+        queue.changed.notified().await;
+        let queue = queue.clone();
+        if let Err(error) = tokio::task::spawn_blocking(move || queue.save()).await {
+            log::warn!("Unable to store the queue: {}", error);
+        }
+    }
+}
+
+/// Translates one job after the other, forever. Translations end up in the client's store,
+/// where the next request for the Pokemon finds them.
+pub(crate) async fn drain(queue: Arc<TranslationQueue>, clients: Reloadable<Clients>) {
+    loop {
+        let job = match queue.peek() {
+            Some(job) => job,
+            None => {
+                queue.added.notified().await;
+                continue;
+            }
+        };
+
+        let wait = match clients
+            .get()
+            .translation
+            .translate(&job.text, job.language)
+            .await
+        {
+            Ok(_) => {
+                queue.remove(&job);
+                queue.interval
+            }
+            // The job stays at the front of the queue until the quota allows it
+            Err(Error::QuotaExhausted(wait)) => wait,
+            Err(Error::RateLimitHit) => queue.interval,
+            Err(error) => {
+                log::warn!("Dropping the {} translation: {}", job.language, error);
+                queue.remove(&job);
+                queue.interval
+            }
+        };
+
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn queue(file: Option<PathBuf>) -> TranslationQueue {
+        TranslationQueue::from(QueueSettings {
+            interval: Duration::from_secs(60),
+            file,
+        })
+    }
+
+    #[test]
+    fn asks_for_the_same_translation_only_once() {
+        let queue = queue(None);
+
+        queue.push("It sometimes appears above ground.", Language::Yoda, None);
+        queue.push("It sometimes appears above ground.", Language::Yoda, None);
+        queue.push("It sometimes appears above ground.", Language::Pirate, None);
+
+        let jobs = queue.lock().clone();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].requests, 2);
+    }
+
+    #[test]
+    fn takes_at_least_until_the_used_up_quota_resets() {
+        let queue = queue(None);
+
+        assert_eq!(
+            queue.push(
+                "A strange seed",
+                Language::Shakespear,
+                Some(Duration::from_secs(40 * 60))
+            ),
+            Duration::from_secs(40 * 60)
+        );
+        assert_eq!(
+            queue.push(
+                "Lives about one yard underground",
+                Language::Yoda,
+                Some(Duration::from_secs(30))
+            ),
+            Duration::from_secs(120)
+        );
+    }
+
+    #[test]
+    fn popular_translations_come_first() {
+        let queue = queue(None);
+
+        assert_eq!(
+            queue.push("A strange seed", Language::Shakespear, None),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            queue.push("Lives about one yard underground", Language::Yoda, None),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            queue.push("Lives about one yard underground", Language::Yoda, None),
+            Duration::from_secs(60)
+        );
+
+        assert_eq!(
+            queue.peek().map(|job| job.text),
+            Some("Lives about one yard underground".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn survives_restarts() {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("queue.json");
+
+        let first = Arc::new(queue(Some(path.clone())));
+        tokio::spawn(persist(first.clone()));
+        first.push("A strange seed", Language::Shakespear, None);
+        first.push("Lives about one yard underground", Language::Yoda, None);
+        first.remove(&Job {
+            language: Language::Shakespear,
+            text: "A strange seed".into(),
+            requests: 1,
+        });
+
+        // Pushing doesn't wait for the file to be written
+        for _ in 0..100 {
+            if load(&path).len() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let second = queue(Some(path));
+        assert_eq!(
+            second.peek(),
+            Some(Job {
+                language: Language::Yoda,
+                text: "Lives about one yard underground".into(),
+                requests: 1,
+            })
+        );
+    }
+}
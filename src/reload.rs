@@ -0,0 +1,220 @@
+//! Rebuilds the clients whenever the configuration changes, either on SIGHUP or when the
+//! configuration file is saved, without restarting the server. The server itself and the
+//! translation queue keep the settings they started with.
+
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::config::Args;
+use crate::server::Clients;
+use crate::Settings;
+
+/// How often to look at the configuration file for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A value that can be replaced while it is in use. Whoever got hold of the old one keeps it
+/// until they are done, such as requests that were in flight during a reload.
+#[derive(Debug)]
+pub(crate) struct Reloadable<T>(Arc<RwLock<Arc<T>>>);
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Reloadable(self.0.clone())
+    }
+}
+
+impl<T> Reloadable<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Reloadable(Arc::new(RwLock::new(Arc::new(value))))
+    }
+
+    pub(crate) fn get(&self) -> Arc<T> {
+        self.0
+            .read()
+            .expect("lock for the reloadable value was poisoned")
+            .clone()
+    }
+
+    fn replace(&self, value: T) {
+        *self
+            .0
+            .write()
+            .expect("lock for the reloadable value was poisoned") = Arc::new(value);
+    }
+}
+
+/// Reloads the configuration on SIGHUP or when its file was modified, forever. Platforms without
+/// SIGHUP only watch the file. `started_with` are the settings the server was started with.
+pub(crate) async fn watch(args: Args, started_with: Settings, clients: Reloadable<Clients>) {
+    #[cfg(unix)]
+    let mut hangup = signal(SignalKind::hangup()).expect("Unable to listen for SIGHUP");
+    let mut last_modified = args.config.as_deref().and_then(modified);
+
+    loop {
+        #[cfg(unix)]
+        let hung_up = hangup.recv();
+        #[cfg(not(unix))]
+        let hung_up = std::future::pending::<Option<()>>();
+
+        tokio::select! {
+            _ = hung_up => log::info!("Reloading the configuration after SIGHUP"),
+            _ = tokio::time::sleep(WATCH_INTERVAL) => {
+                let modified = args.config.as_deref().and_then(modified);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+                log::info!("Reloading the configuration as its file changed");
+            }
+        }
+
+        reload(&args, &started_with, &clients).await;
+    }
+}
+
+/// Swaps the clients for ones built from the current configuration, unless it is invalid.
+/// The new clients keep the caches and the translation quota of the current ones, unless the
+/// settings they belong to changed. The file is read on a blocking thread.
+async fn reload(args: &Args, started_with: &Settings, clients: &Reloadable<Clients>) {
+    let args = args.clone();
+    match tokio::task::spawn_blocking(move || args.settings()).await {
+        Ok(Ok(settings)) => {
+            if settings.server != started_with.server {
+                log::warn!("The server settings only change with a restart");
+            }
+            if settings.translation_api.queue != started_with.translation_api.queue {
+                log::warn!("The translation queue settings only change with a restart");
+            }
+            clients.replace(clients.get().reconfigured(&settings));
+            log::info!("Reloaded the configuration");
+        }
+        Ok(Err(error)) => log::error!("Keeping the current configuration: {}", error),
+        Err(error) => log::error!("Unable to reload the configuration: {}", error),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|file| file.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mocks::setup_translation_api;
+    use crate::translation::{Error, Language};
+    use claim::assert_matches;
+    use pretty_assertions::assert_eq;
+
+    fn config(rules: &str) -> String {
+        format!(
+            r#"
+            poke_api:
+              base_url: https://pokeapi.co
+              timeout: 10s
+            translation_api:
+              base_url: https://api.funtranslations.com
+              timeout: 10s
+              rules: {}
+            "#,
+            rules
+        )
+    }
+
+    fn rules(clients: &Clients) -> serde_json::Value {
+        serde_json::to_value(&clients.rules).expect("Unable to serialize the rules")
+    }
+
+    #[tokio::test]
+    async fn only_new_requests_get_the_reloaded_clients() {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("poke.yml");
+        std::fs::write(&path, config(r#"[{ style: yoda }]"#)).expect("Unable to write config");
+        let args = Args {
+            config: Some(path.clone()),
+            ..Args::default()
+        };
+        let settings = args.settings().expect("Unable to load the settings");
+        let clients = Reloadable::new(Clients::from(&settings));
+        let in_flight = clients.get();
+
+        std::fs::write(&path, config(r#"[{ style: pirate }]"#)).expect("Unable to write config");
+        reload(&args, &settings, &clients).await;
+
+        assert_eq!(rules(&in_flight)["rules"][0]["style"], "yoda");
+        assert_eq!(rules(&clients.get())["rules"][0]["style"], "pirate");
+    }
+
+    #[tokio::test]
+    async fn keeps_the_current_clients_when_the_new_configuration_is_invalid() {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("poke.yml");
+        std::fs::write(&path, config("[]")).expect("Unable to write config");
+        let args = Args {
+            config: Some(path.clone()),
+            ..Args::default()
+        };
+        let settings = args.settings().expect("Unable to load the settings");
+        let clients = Reloadable::new(Clients::from(&settings));
+        let before = clients.get();
+
+        std::fs::write(&path, config("[{ style: not-a-style }]")).expect("Unable to write config");
+        reload(&args, &settings, &clients).await;
+
+        assert!(Arc::ptr_eq(&before, &clients.get()));
+    }
+
+    #[tokio::test]
+    async fn the_translation_quota_survives_a_reload() {
+        let translation_mock = setup_translation_api().await;
+        let config = |timeout: &str| {
+            format!(
+                r#"
+                poke_api:
+                  base_url: https://pokeapi.co
+                  timeout: 10s
+                translation_api:
+                  base_url: {}
+                  timeout: {}
+                "#,
+                translation_mock.settings().base_url,
+                timeout
+            )
+        };
+
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("poke.yml");
+        std::fs::write(&path, config("10s")).expect("Unable to write config");
+        let args = Args {
+            config: Some(path.clone()),
+            ..Args::default()
+        };
+        let settings = args.settings().expect("Unable to load the settings");
+        let clients = Reloadable::new(Clients::from(&settings));
+
+        // Only asked once, the second translation knows better than to try
+        translation_mock.has_hit_rate_limit().await;
+        let error = clients
+            .get()
+            .translation
+            .translate("Hello there", Language::Yoda)
+            .await
+            .expect_err("The rate limit should have been hit");
+        assert_matches!(error, Error::QuotaExhausted(_));
+
+        std::fs::write(&path, config("5s")).expect("Unable to write config");
+        reload(&args, &settings, &clients).await;
+
+        let error = clients
+            .get()
+            .translation
+            .translate("Hello there", Language::Yoda)
+            .await
+            .expect_err("The quota should still be used up");
+        assert_matches!(error, Error::QuotaExhausted(_));
+    }
+}
@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+
+use crate::server::Pokemon;
+use crate::translation::Language;
+
+/// Used when none of the rules match
+pub(crate) const FALLBACK: Language = Language::Shakespear;
+
+/// What a Pokemon has to look like for a rule to apply. Every condition that is set has to hold,
+/// so a rule without any conditions matches all Pokemon.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Conditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) habitat: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) legendary: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mythical: Option<bool>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub(crate) kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) generation: Option<String>,
+}
+
+impl Conditions {
+    fn matches(&self, pokemon: &Pokemon) -> bool {
+        // A Pokemon without a habitat doesn't match a rule about one
+        let same = |expected: &Option<String>, actual: Option<&str>| {
+            expected.as_ref().is_none_or(|expected| {
+                actual.is_some_and(|actual| expected.eq_ignore_ascii_case(actual))
+            })
+        };
+        // Without battle data we can't tell the types, so a rule about them doesn't apply
+        let has_type = self.kind.as_ref().is_none_or(|kind| {
+            pokemon
+                .battle
+                .as_ref()
+                .is_some_and(|battle| battle.types.iter().any(|t| t.eq_ignore_ascii_case(kind)))
+        });
+
+        same(&self.habitat, pokemon.habitat.as_deref())
+            && self.legendary.is_none_or(|l| l == pokemon.is_legendary)
+            && self
+                .mythical
+                .is_none_or(|m| m == pokemon.details.is_mythical)
+            && has_type
+            && same(&self.color, Some(&pokemon.details.color))
+            && same(&self.generation, Some(&pokemon.details.generation))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Rule {
+    #[serde(default)]
+    pub(crate) when: Conditions,
+    pub(crate) style: Language,
+}
+
+/// The rules we used before they became configurable
+pub(crate) fn default_rules() -> Vec<Rule> {
+    vec![
+        Rule {
+            when: Conditions {
+                legendary: Some(true),
+                ..Conditions::default()
+            },
+            style: Language::Yoda,
+        },
+        Rule {
+            when: Conditions {
+                habitat: Some("cave".into()),
+                ..Conditions::default()
+            },
+            style: Language::Yoda,
+        },
+    ]
+}
+
+/// Why a Pokemon was translated the way it was
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Reason {
+    /// Asked for with `?style=`
+    Requested,
+    Rule,
+    Fallback,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Explanation {
+    pub(crate) style: Language,
+    pub(crate) reason: Reason,
+    /// The position of the rule that matched, starting at 0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rule: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) when: Option<Conditions>,
+}
+
+/// The ordered list of rules where the first one to match picks the translation.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Rules {
+    rules: Vec<Rule>,
+    fallback: Language,
+}
+
+impl From<Vec<Rule>> for Rules {
+    fn from(rules: Vec<Rule>) -> Self {
+        Rules {
+            rules,
+            fallback: FALLBACK,
+        }
+    }
+}
+
+impl Rules {
+    pub(crate) fn pick(&self, pokemon: &Pokemon) -> Explanation {
+        let matched = self
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.when.matches(pokemon));
+
+        match matched {
+            Some((index, rule)) => Explanation {
+                style: rule.style,
+                reason: Reason::Rule,
+                rule: Some(index),
+                when: Some(rule.when.clone()),
+            },
+            None => Explanation {
+                style: self.fallback,
+                reason: Reason::Fallback,
+                rule: None,
+                when: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{Battle, Details};
+    use pretty_assertions::assert_eq;
+
+    fn pokemon(habitat: &str, is_legendary: bool, types: &[&str]) -> Pokemon {
+        Pokemon {
+            id: 1,
+            name: "missingno".into(),
+            description: "".into(),
+            language: "en".into(),
+            version: "red".into(),
+            habitat: Some(habitat.into()),
+            is_legendary,
+            details: Details {
+                color: "green".into(),
+                generation: "generation-i".into(),
+                ..Details::default()
+            },
+            battle: Some(Battle {
+                types: types.iter().map(|t| t.to_string()).collect(),
+                stats: Vec::new(),
+                abilities: Vec::new(),
+                height: 0,
+                weight: 0,
+            }),
+        }
+    }
+
+    #[test]
+    fn the_default_rules_prefer_yoda_for_legendary_and_cave_pokemon() {
+        let rules = Rules::from(default_rules());
+
+        assert_eq!(
+            rules.pick(&pokemon("rare", true, &["psychic"])).rule,
+            Some(0)
+        );
+        assert_eq!(
+            rules.pick(&pokemon("cave", false, &["ground"])).rule,
+            Some(1)
+        );
+        assert_eq!(
+            rules.pick(&pokemon("grassland", false, &["grass"])),
+            Explanation {
+                style: Language::Shakespear,
+                reason: Reason::Fallback,
+                rule: None,
+                when: None,
+            }
+        );
+    }
+
+    #[test]
+    fn all_conditions_of_a_rule_have_to_match() {
+        let rules = Rules::from(vec![Rule {
+            when: Conditions {
+                kind: Some("Water".into()),
+                color: Some("green".into()),
+                ..Conditions::default()
+            },
+            style: Language::Pirate,
+        }]);
+
+        assert_eq!(
+            rules
+                .pick(&pokemon("sea", false, &["grass", "water"]))
+                .style,
+            Language::Pirate
+        );
+        assert_eq!(
+            rules.pick(&pokemon("sea", false, &["grass"])).style,
+            Language::Shakespear
+        );
+    }
+
+    #[test]
+    fn rules_about_the_habitat_skip_pokemon_without_one() {
+        let rules = Rules::from(default_rules());
+        let lucario = Pokemon {
+            habitat: None,
+            ..pokemon("", false, &["fighting", "steel"])
+        };
+
+        assert_eq!(rules.pick(&lucario).style, Language::Shakespear);
+    }
+
+    #[test]
+    fn deserializes_rules_from_config() {
+        let rules = serde_json::from_str::<Vec<Rule>>(
+            r#"[
+                { "when": { "mythical": true, "generation": "generation-i" }, "style": "sith" },
+                { "style": "minion" }
+            ]"#,
+        )
+        .expect("Unable to deserialize rules");
+
+        assert_eq!(
+            rules,
+            vec![
+                Rule {
+                    when: Conditions {
+                        mythical: Some(true),
+                        generation: Some("generation-i".into()),
+                        ..Conditions::default()
+                    },
+                    style: Language::Sith,
+                },
+                Rule {
+                    when: Conditions::default(),
+                    style: Language::Minion,
+                }
+            ]
+        );
+    }
+}
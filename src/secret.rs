@@ -0,0 +1,56 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// Something like an API key that must never end up in the logs, so its `Debug` is redacted.
+#[derive(Clone, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub(crate) struct Secret(String);
+
+impl Secret {
+    #[cfg(test)]
+    pub(crate) fn new<S: Into<String>>(secret: S) -> Self {
+        Secret(secret.into())
+    }
+
+    /// Reads the secret from a file such as a mounted Docker or Kubernetes secret,
+    /// ignoring the trailing newline most editors add.
+    pub(crate) fn from_file(path: &Path) -> std::io::Result<Self> {
+        let secret = std::fs::read_to_string(path)?;
+        Ok(Secret(secret.trim().to_string()))
+    }
+
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn does_not_show_the_secret_when_debugging() {
+        let secret = Secret::new("very-secret");
+
+        assert_eq!(format!("{:?}", secret), "Secret(<redacted>)");
+        assert_eq!(secret.expose(), "very-secret");
+    }
+
+    #[test]
+    fn reads_the_secret_from_a_file() {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("secret");
+        std::fs::write(&path, "very-secret\n").expect("Unable to write the secret");
+
+        let secret = Secret::from_file(&path).expect("Unable to read the secret");
+
+        assert_eq!(secret, Secret::new("very-secret"));
+    }
+}
@@ -1,24 +1,314 @@
-use crate::pokeapi::{Error, PokeClient};
-use crate::translation::{Language, TranslationClient};
+use crate::cache::CacheStats;
+use crate::local;
+use crate::pokeapi::{Error, PokeClient, Preferences, VersionStrategy};
+use crate::queue::{self, TranslationQueue};
+use crate::reload::Reloadable;
+use crate::rules::{Explanation, Reason, Rules};
+use crate::translation::{
+    Error as TranslationError, Kind, Language, Translated, TranslationClient,
+};
 use crate::Settings;
 
+use rocket::config::{Config, TlsConfig};
+use rocket::fairing::AdHoc;
 use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::serde::json::{self, Json};
 use rocket::Request;
-use rocket::{serde::json::Json, Build, Rocket, State};
-use serde::Serialize;
+use rocket::{Build, Rocket, State};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Where and how the server listens. Anything that isn't set is left to Rocket,
+/// including its `ROCKET_*` environment variables.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub(crate) struct ServerSettings {
+    #[serde(default)]
+    pub(crate) address: Option<IpAddr>,
+    #[serde(default)]
+    pub(crate) port: Option<u16>,
+    #[serde(default)]
+    pub(crate) workers: Option<usize>,
+    /// How long idle connections are kept open, 0s disables keep-alive
+    #[serde(default, with = "humantime_serde")]
+    pub(crate) keep_alive: Option<Duration>,
+    /// Serves HTTPS instead of HTTP, if set
+    #[serde(default)]
+    pub(crate) tls: Option<TlsSettings>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub(crate) struct TlsSettings {
+    /// The PEM-encoded certificate chain
+    pub(crate) certs: PathBuf,
+    /// The PEM-encoded private key
+    pub(crate) key: PathBuf,
+}
+
+impl ServerSettings {
+    fn figment(&self) -> rocket::figment::Figment {
+        let mut figment = Config::figment();
+        if let Some(address) = self.address {
+            figment = figment.merge((Config::ADDRESS, address));
+        }
+        if let Some(port) = self.port {
+            figment = figment.merge((Config::PORT, port));
+        }
+        if let Some(workers) = self.workers {
+            figment = figment.merge((Config::WORKERS, workers));
+        }
+        if let Some(keep_alive) = self.keep_alive {
+            figment = figment.merge((Config::KEEP_ALIVE, keep_alive.as_secs()));
+        }
+        if let Some(tls) = &self.tls {
+            figment = figment.merge((Config::TLS, TlsConfig::from_paths(&tls.certs, &tls.key)));
+        }
+        figment
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct Pokemon {
+    pub id: u32,
     pub name: String,
     pub description: String,
-    pub habitat: String,
+    pub language: String,
+    pub version: String,
+    pub habitat: Option<String>,
     #[serde(rename = "isLegendary")]
     pub is_legendary: bool,
+    #[serde(skip)]
+    pub details: Details,
+    #[serde(flatten)]
+    pub battle: Option<Battle>,
+}
+
+/// What matters in battle, which PokeAPI keeps apart from the species data.
+#[derive(Clone, Debug, Serialize)]
+pub struct Battle {
+    pub types: Vec<String>,
+    pub stats: Vec<Stat>,
+    pub abilities: Vec<Ability>,
+    /// In decimetres
+    pub height: u32,
+    /// In hectograms
+    pub weight: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Stat {
+    pub name: String,
+    pub base: u8,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Ability {
+    pub name: String,
+    #[serde(rename = "isHidden")]
+    pub is_hidden: bool,
+}
+
+/// The rest of the species data, which is only part of the full representation of a Pokemon.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Details {
+    pub genus: Option<String>,
+    pub color: String,
+    pub shape: Option<String>,
+    pub capture_rate: u8,
+    pub base_happiness: Option<u8>,
+    pub gender_rate: i8,
+    pub hatch_counter: Option<u8>,
+    pub growth_rate: String,
+    pub egg_groups: Vec<String>,
+    pub generation: String,
+    pub is_mythical: bool,
+    pub is_baby: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FullPokemon {
+    #[serde(flatten)]
+    pub pokemon: Pokemon,
+    #[serde(flatten)]
+    pub details: Details,
+}
+
+impl From<Pokemon> for FullPokemon {
+    fn from(mut pokemon: Pokemon) -> Self {
+        let details = std::mem::take(&mut pokemon.details);
+        FullPokemon { pokemon, details }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranslatedPokemon {
+    #[serde(flatten)]
+    pub pokemon: Pokemon,
+    pub translation: Translation,
+    /// Only given when asked for with `?explain=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
+}
+
+/// How the description of a translated Pokemon came to be.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Translation {
+    pub language: Language,
+    pub status: TranslationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_reason: Option<FallbackReason>,
+    /// Whether the translation service was unavailable and we translated the description ourselves
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub translated_locally: bool,
+    /// The description before it was translated
+    pub original: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationStatus {
+    Translated,
+    /// Translated before, so the translation service wasn't asked again
+    Cached,
+    /// Will be translated in the background, see the `Retry-After` header for when to ask again
+    Queued,
+    Fallback,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackReason {
+    RateLimited,
+    UpstreamError,
+    BadJson,
+    /// No translation service is configured for the language
+    Unsupported,
+}
+
+impl From<&TranslationError> for FallbackReason {
+    fn from(error: &TranslationError) -> Self {
+        match error {
+            TranslationError::RateLimitHit | TranslationError::QuotaExhausted(_) => {
+                FallbackReason::RateLimited
+            }
+            TranslationError::BadJson => FallbackReason::BadJson,
+            TranslationError::Unsupported(_) => FallbackReason::Unsupported,
+            TranslationError::Other(_) => FallbackReason::UpstreamError,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Description {
+    pub description: String,
+    pub language: String,
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Descriptions {
+    pub name: String,
+    pub descriptions: Vec<Description>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvolutionChain {
+    pub name: String,
+    pub evolves_from: Option<String>,
+    pub chain: Evolution,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evolution {
+    pub name: String,
+    pub is_baby: bool,
+    /// What it takes to evolve into this Pokemon. Empty for the start of the chain.
+    pub triggers: Vec<EvolutionTrigger>,
+    pub evolves_to: Vec<Evolution>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvolutionTrigger {
+    pub trigger: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub held_item: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_happiness: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_affection: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_of_day: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_move: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// Longest text `/translate/<style>` accepts, as every translation uses up some of the quota
+const MAX_TEXT_LENGTH: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+struct TextToTranslate {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TranslatedText {
+    text: String,
+    style: Language,
+    status: TranslationStatus,
+    original: String,
 }
 
 #[derive(Serialize)]
 struct ApiError {
     message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<String>,
+}
+
+/// How well the caches in front of PokeAPI are doing.
+#[derive(Serialize)]
+struct CacheReport {
+    species: CacheStats,
+    battle: CacheStats,
+}
+
+/// Tells clients when to ask again with a `Retry-After` header, if there is anything to wait for.
+struct RetryAfter<R> {
+    inner: R,
+    after: Option<Duration>,
+}
+
+impl<R> RetryAfter<R> {
+    fn never(inner: R) -> Self {
+        RetryAfter { inner, after: None }
+    }
+}
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for RetryAfter<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = self.inner.respond_to(request)?;
+        if let Some(after) = self.after {
+            // Whole seconds, rounded up so clients don't ask too early
+            let seconds = after.as_secs() + u64::from(after.subsec_nanos() > 0);
+            response.set_raw_header("Retry-After", seconds.to_string());
+        }
+        Ok(response)
+    }
 }
 
 type ApiResult<T> = Result<Json<T>, (Status, Json<ApiError>)>;
@@ -27,8 +317,44 @@ fn ok<T>(value: T) -> ApiResult<T> {
     Result::Ok(Json(value))
 }
 
-fn not_found<T>(message: String) -> ApiResult<T> {
-    Result::Err((Status::NotFound, Json(ApiError { message })))
+fn not_found<T>(message: String, suggestions: Vec<String>) -> ApiResult<T> {
+    Result::Err((
+        Status::NotFound,
+        Json(ApiError {
+            message,
+            suggestions,
+        }),
+    ))
+}
+
+fn bad_request<T>(message: String) -> ApiResult<T> {
+    Result::Err((
+        Status::BadRequest,
+        Json(ApiError {
+            message,
+            suggestions: Vec::new(),
+        }),
+    ))
+}
+
+fn payload_too_large<T>(message: String) -> ApiResult<T> {
+    Result::Err((
+        Status::PayloadTooLarge,
+        Json(ApiError {
+            message,
+            suggestions: Vec::new(),
+        }),
+    ))
+}
+
+fn too_many_requests<T>(message: String) -> ApiResult<T> {
+    Result::Err((
+        Status::TooManyRequests,
+        Json(ApiError {
+            message,
+            suggestions: Vec::new(),
+        }),
+    ))
 }
 
 fn internal_server_error<T>() -> ApiResult<T> {
@@ -36,68 +362,391 @@ fn internal_server_error<T>() -> ApiResult<T> {
         Status::InternalServerError,
         Json(ApiError {
             message: "Internal server error".into(),
+            suggestions: Vec::new(),
         }),
     ))
 }
 
-#[rocket::get("/pokemon/<name>")]
-async fn find_pokemon(poke_api: &State<PokeClient>, name: &str) -> ApiResult<Pokemon> {
-    match poke_api.find(name).await {
-        Ok(pokemon) => ok(pokemon),
-        Err(Error::NoSuchPokemon) => {
+async fn lookup_failed<T>(poke_api: &PokeClient, name: &str, error: Error) -> ApiResult<T> {
+    match error {
+        Error::NoSuchPokemon => {
             log::info!("Did not find a pokemon called '{}'", name);
-            not_found(format!("Unable to find '{}'", name))
+            let suggestions = poke_api.suggestions(name).await;
+            not_found(format!("Unable to find '{}'", name), suggestions)
         }
-        Err(error) => {
+        Error::NoDescription(tried) => {
+            log::info!("No description of '{}' in {}", name, tried);
+            not_found(
+                format!("'{}' has no description in any of: {}", name, tried),
+                Vec::new(),
+            )
+        }
+        error => {
             log::info!("Error when looking for '{}': {}", name, error);
             internal_server_error()
         }
     }
 }
 
-#[rocket::get("/pokemon/translated/<name>")]
-async fn find_translated_pokemon(
-    poke_api: &State<PokeClient>,
-    translation_api: &State<TranslationClient>,
+/// The languages from the `Accept-Language` header, ordered by their quality value.
+struct AcceptLanguage(Vec<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptLanguage {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let languages = req
+            .headers()
+            .get_one("Accept-Language")
+            .map(parse_accept_language)
+            .unwrap_or_default();
+
+        Outcome::Success(AcceptLanguage(languages))
+    }
+}
+
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let tag = parts.next().filter(|tag| !tag.is_empty() && *tag != "*")?;
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+
+            Some((tag.to_string(), quality))
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect::<Vec<_>>();
+
+    // The sort is stable, so languages with the same quality keep the order they were sent in
+    weighted.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+fn preferences(
+    lang: Option<String>,
+    version: Option<String>,
+    accept_language: AcceptLanguage,
+) -> Preferences {
+    // `?version=` takes either the name of a game or one of the strategies such as "latest"
+    let (version, strategy) = match version.as_deref().map(str::parse::<VersionStrategy>) {
+        Some(Ok(strategy)) => (None, Some(strategy)),
+        _ => (version, None),
+    };
+
+    Preferences {
+        languages: lang.into_iter().chain(accept_language.0).collect(),
+        version,
+        strategy,
+    }
+}
+
+#[rocket::get("/pokemon/<name>?<lang>&<version>")]
+async fn find_pokemon(
+    clients: &State<Reloadable<Clients>>,
+    name: &str,
+    lang: Option<String>,
+    version: Option<String>,
+    accept_language: AcceptLanguage,
+) -> ApiResult<Pokemon> {
+    let clients = clients.get();
+    let preferences = preferences(lang, version, accept_language);
+
+    match clients.poke_api.find(name, &preferences).await {
+        Ok(pokemon) => ok(pokemon),
+        Err(error) => lookup_failed(&clients.poke_api, name, error).await,
+    }
+}
+
+// Ranked below `/pokemon/translated/<name>`, which would otherwise collide for "translated/full"
+#[rocket::get("/pokemon/<name>/full?<lang>&<version>", rank = 2)]
+async fn find_full_pokemon(
+    clients: &State<Reloadable<Clients>>,
     name: &str,
+    lang: Option<String>,
+    version: Option<String>,
+    accept_language: AcceptLanguage,
+) -> ApiResult<FullPokemon> {
+    let clients = clients.get();
+    let preferences = preferences(lang, version, accept_language);
+
+    match clients.poke_api.find(name, &preferences).await {
+        Ok(pokemon) => ok(pokemon.into()),
+        Err(error) => lookup_failed(&clients.poke_api, name, error).await,
+    }
+}
+
+// Ranked below all other `/pokemon/<name>/...` routes, as something like "mewtwo/full" or
+// "translated/mewtwo" can't be a regional Pokédex and a number anyway.
+#[rocket::get("/pokemon/<pokedex>/<number>?<lang>&<version>", rank = 3)]
+async fn find_in_pokedex(
+    clients: &State<Reloadable<Clients>>,
+    pokedex: &str,
+    number: u32,
+    lang: Option<String>,
+    version: Option<String>,
+    accept_language: AcceptLanguage,
 ) -> ApiResult<Pokemon> {
-    match poke_api.find(name).await {
+    let clients = clients.get();
+    let preferences = preferences(lang, version, accept_language);
+
+    match clients
+        .poke_api
+        .find_in_pokedex(pokedex, number, &preferences)
+        .await
+    {
+        Ok(pokemon) => ok(pokemon),
+        Err(Error::NoSuchPokemon) => {
+            log::info!("Did not find #{} in the {} Pokédex", number, pokedex);
+            not_found(
+                format!("Unable to find #{} in the {} Pokédex", number, pokedex),
+                Vec::new(),
+            )
+        }
+        Err(error) => lookup_failed(&clients.poke_api, pokedex, error).await,
+    }
+}
+
+// Ranked below `/pokemon/translated/<name>`, which would otherwise collide for "translated/descriptions"
+#[rocket::get("/pokemon/<name>/descriptions", rank = 2)]
+async fn find_descriptions(
+    clients: &State<Reloadable<Clients>>,
+    name: &str,
+) -> ApiResult<Descriptions> {
+    let clients = clients.get();
+    match clients.poke_api.descriptions(name).await {
+        Ok(descriptions) => ok(descriptions),
+        Err(error) => lookup_failed(&clients.poke_api, name, error).await,
+    }
+}
+
+// Ranked below `/pokemon/translated/<name>`, which would otherwise collide for "translated/evolutions"
+#[rocket::get("/pokemon/<name>/evolutions", rank = 2)]
+async fn find_evolutions(
+    clients: &State<Reloadable<Clients>>,
+    name: &str,
+) -> ApiResult<EvolutionChain> {
+    let clients = clients.get();
+    match clients.poke_api.evolutions(name).await {
+        Ok(evolutions) => ok(evolutions),
+        Err(error) => lookup_failed(&clients.poke_api, name, error).await,
+    }
+}
+
+#[rocket::get("/cache/stats")]
+fn cache_stats(clients: &State<Reloadable<Clients>>) -> Json<CacheReport> {
+    let clients = clients.get();
+    Json(CacheReport {
+        species: clients.poke_api.species_cache_stats(),
+        battle: clients.poke_api.battle_cache_stats(),
+    })
+}
+
+/// What became of a translation that was asked for
+enum TranslationAttempt {
+    /// Translated right away, or failed trying
+    Immediate(Result<Translated, TranslationError>),
+    /// Left to the queue, which should be done after the given time
+    Queued(Duration),
+}
+
+#[rocket::get("/pokemon/translated/<name>?<style>&<explain>")]
+async fn find_translated_pokemon(
+    clients: &State<Reloadable<Clients>>,
+    queue: &State<Option<Arc<TranslationQueue>>>,
+    name: &str,
+    style: Option<&str>,
+    explain: Option<bool>,
+) -> RetryAfter<ApiResult<TranslatedPokemon>> {
+    let style = match style.map(str::parse::<Language>).transpose() {
+        Ok(style) => style,
+        Err(message) => return RetryAfter::never(bad_request(message)),
+    };
+
+    let clients = clients.get();
+    // The translation APIs only understand English
+    let preferences = Preferences {
+        languages: vec!["en".into()],
+        ..Preferences::default()
+    };
+
+    match clients.poke_api.find(name, &preferences).await {
         Ok(mut pokemon) => {
-            let lang = if pokemon.is_legendary || &pokemon.habitat == "cave" {
-                Language::Yoda
-            } else {
-                Language::Shakespear
+            let explanation = match style {
+                Some(style) => Explanation {
+                    style,
+                    reason: Reason::Requested,
+                    rule: None,
+                    when: None,
+                },
+                None => clients.rules.pick(&pokemon),
             };
+            let lang = explanation.style;
             log::info!("Using the '{}' translation for {}", &lang, pokemon.name);
 
-            let possible_translation = translation_api.translate(&pokemon.description, lang).await;
+            let original = pokemon.description.clone();
+            // Without a queue we wait for the translation, with one only what was translated
+            // before is used and the rest is translated in the background. Only FunTranslations
+            // is limited that much, so the natural languages are always translated right away.
+            let attempt = match queue.inner() {
+                Some(queue) if lang.kind() == Kind::Fun => {
+                    match clients.translation.stored(&original, lang) {
+                        Some(translated) => TranslationAttempt::Immediate(Ok(translated)),
+                        None => TranslationAttempt::Queued(queue.push(
+                            &original,
+                            lang,
+                            clients.translation.exhausted_for(),
+                        )),
+                    }
+                }
+                _ => TranslationAttempt::Immediate(
+                    clients.translation.translate(&original, lang).await,
+                ),
+            };
 
-            match possible_translation {
-                Ok(translated) => {
+            let mut retry_after = None;
+            let translation = match attempt {
+                TranslationAttempt::Immediate(Ok(translated)) => {
                     log::info!("Successfull translation for {}", pokemon.name);
-                    pokemon.description = translated;
+                    pokemon.description = translated.text;
+                    Translation {
+                        language: lang,
+                        status: if translated.cached {
+                            TranslationStatus::Cached
+                        } else {
+                            TranslationStatus::Translated
+                        },
+                        fallback_reason: None,
+                        translated_locally: false,
+                        original,
+                    }
+                }
+                TranslationAttempt::Queued(after) => {
+                    log::info!("Queued the '{}' translation for {}", &lang, pokemon.name);
+                    retry_after = Some(after);
+
+                    let local_translation = local::translate(&original, lang);
+                    let translated_locally = local_translation.is_some();
+                    if let Some(translated) = local_translation {
+                        pokemon.description = translated;
+                    }
+                    Translation {
+                        language: lang,
+                        status: TranslationStatus::Queued,
+                        fallback_reason: None,
+                        translated_locally,
+                        original,
+                    }
                 }
-                Err(error) => {
-                    log::error!(
-                        "Failed to retrieve translation, falling back to standard text: {}",
-                        error
-                    );
+                TranslationAttempt::Immediate(Err(error)) => {
+                    let reason = FallbackReason::from(&error);
+                    // Running out of translations is expected with such a small quota
+                    if reason == FallbackReason::RateLimited {
+                        log::info!("Falling back to a local translation: {}", error);
+                    } else {
+                        log::error!(
+                            "Failed to retrieve translation, falling back to a local translation: {}",
+                            error
+                        );
+                    }
+
+                    let local_translation = local::translate(&original, lang);
+                    let translated_locally = local_translation.is_some();
+                    if let Some(translated) = local_translation {
+                        pokemon.description = translated;
+                    }
+                    Translation {
+                        language: lang,
+                        status: TranslationStatus::Fallback,
+                        fallback_reason: Some(reason),
+                        translated_locally,
+                        original,
+                    }
                 }
+            };
+
+            RetryAfter {
+                inner: ok(TranslatedPokemon {
+                    pokemon,
+                    translation,
+                    explanation: explain.unwrap_or(false).then_some(explanation),
+                }),
+                after: retry_after,
             }
+        }
+        Err(error) => RetryAfter::never(lookup_failed(&clients.poke_api, name, error).await),
+    }
+}
 
-            ok(pokemon)
+#[rocket::post("/translate/<style>", data = "<body>")]
+async fn translate_text(
+    clients: &State<Reloadable<Clients>>,
+    style: &str,
+    body: Result<Json<TextToTranslate>, json::Error<'_>>,
+) -> RetryAfter<ApiResult<TranslatedText>> {
+    let style = match style.parse::<Language>() {
+        Ok(style) => style,
+        Err(message) => return RetryAfter::never(bad_request(message)),
+    };
+    let original = match body {
+        Ok(body) => body.into_inner().text,
+        Err(json::Error::Parse(_, error)) => {
+            return RetryAfter::never(bad_request(format!(
+                "Expected a JSON body like {{\"text\": \"...\"}}: {}",
+                error
+            )))
         }
-        Err(Error::NoSuchPokemon) => {
-            log::info!("Did not find a pokemon called '{}'", name);
-            not_found(format!("Unable to find '{}'", name))
+        Err(json::Error::Io(error)) => {
+            log::error!("Unable to read the text to translate: {}", error);
+            return RetryAfter::never(internal_server_error());
+        }
+    };
+    if original.trim().is_empty() {
+        return RetryAfter::never(bad_request("There is no text to translate".into()));
+    }
+    if original.chars().count() > MAX_TEXT_LENGTH {
+        return RetryAfter::never(payload_too_large(format!(
+            "Only texts of up to {} characters can be translated",
+            MAX_TEXT_LENGTH
+        )));
+    }
+
+    match clients.get().translation.translate(&original, style).await {
+        Ok(translated) => RetryAfter::never(ok(TranslatedText {
+            text: translated.text,
+            style,
+            status: if translated.cached {
+                TranslationStatus::Cached
+            } else {
+                TranslationStatus::Translated
+            },
+            original,
+        })),
+        Err(TranslationError::QuotaExhausted(wait)) => RetryAfter {
+            inner: too_many_requests(TranslationError::QuotaExhausted(wait).to_string()),
+            after: Some(wait),
+        },
+        Err(error @ TranslationError::RateLimitHit) => {
+            RetryAfter::never(too_many_requests(error.to_string()))
+        }
+        Err(error @ TranslationError::Unsupported(_)) => {
+            RetryAfter::never(bad_request(error.to_string()))
         }
         Err(error) => {
-            log::info!("Error when looking for '{}': {}", name, error);
-            internal_server_error()
+            log::error!("Unable to translate text to '{}': {}", style, error);
+            RetryAfter::never(internal_server_error())
         }
     }
 }
 
+#[rocket::get("/translation/rules")]
+fn translation_rules(clients: &State<Reloadable<Clients>>) -> Json<Rules> {
+    Json(clients.get().rules.clone())
+}
+
 #[derive(Serialize)]
 struct Examples {
     mewtwo: &'static str,
@@ -115,7 +764,7 @@ struct HelpMessage {
 fn help_message(req: &Request) -> Json<HelpMessage> {
     Json(HelpMessage {
         message: format!("Route '{}' was not found", req.uri().path()),
-        help: "There are only two valid routes: '/pokemon/<name>' and '/pokemon/translated/<name>'",
+        help: "The valid routes are: '/pokemon/<name>', '/pokemon/<name>/full', '/pokemon/<name>/descriptions', '/pokemon/<name>/evolutions', '/pokemon/<pokedex>/<number>', '/pokemon/translated/<name>', '/translate/<style>', '/translation/rules' and '/cache/stats'",
         examples: Examples {
             mewtwo: "/pokemon/mewtwo",
             diglett_translated: "/pokemon/translated/diglett",
@@ -123,32 +772,93 @@ fn help_message(req: &Request) -> Json<HelpMessage> {
     })
 }
 
+/// Everything that is rebuilt when the configuration is reloaded
+pub(crate) struct Clients {
+    pub(crate) poke_api: PokeClient,
+    pub(crate) translation: TranslationClient,
+    pub(crate) rules: Rules,
+}
+
+impl From<&Settings> for Clients {
+    fn from(settings: &Settings) -> Self {
+        Clients {
+            poke_api: settings.poke_api_client(),
+            translation: settings.translation_api_client(),
+            rules: settings.translation_rules(),
+        }
+    }
+}
+
+impl Clients {
+    /// Clients for the reloaded `settings` that keep the caches and the translation quota of
+    /// these ones, as far as the settings they depend on are the same
+    pub(crate) fn reconfigured(&self, settings: &Settings) -> Clients {
+        Clients {
+            poke_api: self.poke_api.reconfigured(settings.poke_api.clone()),
+            translation: self
+                .translation
+                .reconfigured(settings.translation_api.clone()),
+            rules: settings.translation_rules(),
+        }
+    }
+}
+
 pub(crate) fn rocket(settings: Settings) -> Rocket<Build> {
-    let poke_api_client = settings.poke_api_client();
-    let translation_client = settings.translation_api_client();
+    let clients = Reloadable::new(Clients::from(&settings));
+    let translation_queue = settings.translation_queue().map(Arc::new);
+
+    let mut rocket = rocket::custom(settings.server.figment());
+    if let Some(queue) = translation_queue.clone() {
+        let clients = clients.clone();
+        rocket = rocket.attach(AdHoc::on_liftoff("Translation queue", |_| {
+            Box::pin(async move {
+                tokio::spawn(queue::persist(queue.clone()));
+                tokio::spawn(queue::drain(queue, clients));
+            })
+        }));
+    }
 
-    rocket::build()
+    rocket
         .register("/", rocket::catchers![help_message])
-        .manage(poke_api_client)
-        .manage(translation_client)
-        .mount("/", rocket::routes![find_pokemon, find_translated_pokemon])
+        .manage(clients)
+        .manage(translation_queue)
+        .mount(
+            "/",
+            rocket::routes![
+                find_pokemon,
+                find_full_pokemon,
+                find_descriptions,
+                find_evolutions,
+                find_in_pokedex,
+                find_translated_pokemon,
+                cache_stats,
+                translate_text,
+                translation_rules
+            ],
+        )
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::mocks::*;
+    use crate::queue::QueueSettings;
     use crate::translation::Language;
     use assert_json_diff::assert_json_eq;
-    use rocket::http::Status;
+    use rocket::http::{Header, Status};
 
     #[test]
     fn serializes_pokemon_responses_to_the_adequate_json() {
         let mewtwo = Pokemon {
+            id: 150,
             name: "mewtwo".into(),
             description: "It was created by scientists after years...".into(),
-            habitat: "rare".into(),
+            language: "en".into(),
+            version: "red".into(),
+            habitat: Some("rare".into()),
             is_legendary: true,
+            details: Details::default(),
+            battle: None,
         };
 
         let actual_json =
@@ -159,8 +869,11 @@ mod test {
             json(
                 r#"
                 {
+                    "id": 150,
                     "name": "mewtwo",
                     "description": "It was created by scientists after years...",
+                    "language": "en",
+                    "version": "red",
                     "habitat":"rare",
                     "isLegendary":true
                 }
@@ -168,6 +881,49 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn the_server_settings_are_passed_to_rocket() {
+        let server = ServerSettings {
+            address: Some("0.0.0.0".parse().unwrap()),
+            port: Some(9000),
+            workers: Some(4),
+            keep_alive: Some(Duration::from_secs(30)),
+            tls: Some(TlsSettings {
+                certs: "/etc/oak/certs.pem".into(),
+                key: "/etc/oak/key.pem".into(),
+            }),
+        };
+
+        let config = server
+            .figment()
+            .extract::<Config>()
+            .expect("Invalid Rocket config");
+
+        assert_eq!(config.address, "0.0.0.0".parse::<IpAddr>().unwrap());
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.workers, 4);
+        assert_eq!(config.keep_alive, 30);
+        assert_eq!(
+            config.tls,
+            Some(TlsConfig::from_paths(
+                "/etc/oak/certs.pem",
+                "/etc/oak/key.pem"
+            ))
+        );
+    }
+
+    #[test]
+    fn rocket_keeps_its_own_defaults_for_everything_else() {
+        let config = ServerSettings::default()
+            .figment()
+            .extract::<Config>()
+            .expect("Invalid Rocket config");
+
+        assert_eq!(config.port, Config::default().port);
+        assert_eq!(config.tls, None);
+    }
+
     #[tokio::test]
     async fn requesting_non_existing_routes_gives_a_helpful_message_with_examples() {
         let (client, _, _) = setup().await;
@@ -186,7 +942,7 @@ mod test {
                 r#"
                 {
                     "message": "Route '/a/random/route' was not found",
-                    "help": "There are only two valid routes: '/pokemon/<name>' and '/pokemon/translated/<name>'",
+                    "help": "The valid routes are: '/pokemon/<name>', '/pokemon/<name>/full', '/pokemon/<name>/descriptions', '/pokemon/<name>/evolutions', '/pokemon/<pokedex>/<number>', '/pokemon/translated/<name>', '/translate/<style>', '/translation/rules' and '/cache/stats'",
                     "examples": {
                         "mewtwo": "/pokemon/mewtwo",
                         "diglett_translated": "/pokemon/translated/diglett"
@@ -216,8 +972,11 @@ mod test {
             json(
                 r#"
                 {
+                    "id": 150,
                     "name": "mewtwo",
                     "description": "It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.",
+                    "language": "en",
+                    "version": "red",
                     "habitat":"rare",
                     "isLegendary":true
                 }
@@ -227,24 +986,49 @@ mod test {
     }
 
     #[tokio::test]
-    async fn lets_users_know_when_pokemon_were_not_found() {
+    async fn the_battle_data_is_merged_into_the_response() {
         let (client, poke_mock, _) = setup().await;
 
-        poke_mock.no_pokemon_exist().await;
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
+        poke_mock
+            .has_battle_data("mewtwo", RAW_MEWTWO_POKEMON)
+            .await;
 
         let response = client.get("/pokemon/mewtwo").dispatch().await;
-        assert_eq!(response.status(), Status::NotFound);
-        let error = response
+
+        assert_eq!(response.status(), Status::Ok);
+        let mewtwo_json = response
             .into_string()
             .await
             .expect("Unexpected empty response");
 
         assert_json_eq!(
-            json(&error),
+            json(&mewtwo_json),
             json(
                 r#"
                 {
-                    "message": "Unable to find 'mewtwo'"
+                    "id": 150,
+                    "name": "mewtwo",
+                    "description": "It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.",
+                    "language": "en",
+                    "version": "red",
+                    "habitat":"rare",
+                    "isLegendary":true,
+                    "types": ["psychic"],
+                    "stats": [
+                        { "name": "hp", "base": 106 },
+                        { "name": "attack", "base": 110 },
+                        { "name": "defense", "base": 90 },
+                        { "name": "special-attack", "base": 154 },
+                        { "name": "special-defense", "base": 90 },
+                        { "name": "speed", "base": 130 }
+                    ],
+                    "abilities": [
+                        { "name": "pressure", "isHidden": false },
+                        { "name": "unnerve", "isHidden": true }
+                    ],
+                    "height": 20,
+                    "weight": 1220
                 }
                 "#
             )
@@ -252,50 +1036,35 @@ mod test {
     }
 
     #[tokio::test]
-    async fn other_errors_result_in_a_500_error() {
+    async fn the_description_honours_the_accept_language_header() {
         let (client, poke_mock, _) = setup().await;
 
-        poke_mock.is_present("mewtwo", "{not_even_json}").await;
-
-        let response = client.get("/pokemon/mewtwo").dispatch().await;
-        assert_eq!(response.status(), Status::InternalServerError);
-
-        let error = response
-            .into_string()
-            .await
-            .expect("Unexpected empty response");
-
-        assert_json_eq!(
-            json(&error),
-            json(r#"{"message": "Internal server error"}"#)
-        );
-    }
-
-    #[tokio::test]
-    async fn when_asking_for_a_cave_pokemon_the_translation_is_in_yoda_speak() {
-        let (client, poke_mock, translation_mock) = setup().await;
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
 
-        poke_mock.is_present("diglett", RAW_DIGLETT).await;
-        translation_mock
-            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+        let response = client
+            .get("/pokemon/mewtwo")
+            .header(Header::new("Accept-Language", "ko;q=0.1, de-DE, en;q=0.8"))
+            .dispatch()
             .await;
 
-        let response = client.get("/pokemon/translated/diglett").dispatch().await;
         assert_eq!(response.status(), Status::Ok);
-        let diglett_json = response
+        let mewtwo_json = response
             .into_string()
             .await
             .expect("Unexpected empty response");
 
         assert_json_eq!(
-            json(&diglett_json),
+            json(&mewtwo_json),
             json(
                 r#"
                 {
-                    "name": "diglett",
-                    "description": "On plant roots,  lives about one yard underground where it feeds.Above ground,  it sometimes appears.",
-                    "habitat":"cave",
-                    "isLegendary":false
+                    "id": 150,
+                    "name": "mewtwo",
+                    "description": "Dieses Pokémon ist das Resultat eines jahrelangen und skrupellosen Experimentes.",
+                    "language": "de",
+                    "version": "x",
+                    "habitat":"rare",
+                    "isLegendary":true
                 }
                 "#
             )
@@ -303,10 +1072,454 @@ mod test {
     }
 
     #[tokio::test]
-    async fn when_asking_for_a_legendary_pokemon_the_translation_is_in_yoda_speak() {
-        let (client, poke_mock, translation_mock) = setup().await;
+    async fn the_lang_query_parameter_overrides_the_accept_language_header() {
+        let (client, poke_mock, _) = setup().await;
 
-        // MewTwo is definitly legendary!
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
+
+        let response = client
+            .get("/pokemon/mewtwo?lang=fr")
+            .header(Header::new("Accept-Language", "de"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let mewtwo_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_eq!(json(&mewtwo_json)["language"], "fr");
+    }
+
+    #[tokio::test]
+    async fn the_description_can_be_chosen_by_game_version() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+
+        let response = client
+            .get("/pokemon/bulbasaur?version=sword")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let bulbasaur_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        let bulbasaur = json(&bulbasaur_json);
+        assert_eq!(bulbasaur["version"], "sword");
+        assert_eq!(bulbasaur["description"], "There is a plant seed on its back right from the day this Pokémon is born. The seed slowly grows larger.");
+    }
+
+    #[tokio::test]
+    async fn the_version_can_also_be_a_strategy() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+
+        let response = client
+            .get("/pokemon/bulbasaur?version=latest")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let bulbasaur_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_eq!(json(&bulbasaur_json)["version"], "shield");
+    }
+
+    #[test]
+    fn orders_accepted_languages_by_their_quality() {
+        assert_eq!(
+            parse_accept_language("ja;q=0.5, fr-CH, fr;q=0.9, *;q=0.1, de;q=0"),
+            vec!["fr-CH".to_string(), "fr".to_string(), "ja".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn the_full_representation_includes_the_rest_of_the_species_data() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
+
+        let response = client.get("/pokemon/mewtwo/full").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let mewtwo_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&mewtwo_json),
+            json(
+                r#"
+                {
+                    "id": 150,
+                    "name": "mewtwo",
+                    "description": "It was created by a scientist after years of horrific gene splicing and DNA engineering experiments.",
+                    "language": "en",
+                    "version": "red",
+                    "habitat": "rare",
+                    "isLegendary": true,
+                    "genus": "Genetic Pokémon",
+                    "color": "purple",
+                    "shape": "upright",
+                    "captureRate": 3,
+                    "baseHappiness": 0,
+                    "genderRate": -1,
+                    "hatchCounter": 120,
+                    "growthRate": "slow",
+                    "eggGroups": ["no-eggs"],
+                    "generation": "generation-i",
+                    "isMythical": false,
+                    "isBaby": false
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_all_descriptions_of_a_pokemon() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+
+        let response = client
+            .get("/pokemon/bulbasaur/descriptions")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let descriptions_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        let descriptions = json(&descriptions_json);
+        assert_eq!(descriptions["name"], "bulbasaur");
+        assert_json_eq!(
+            descriptions["descriptions"][0].clone(),
+            json(
+                r#"
+                {
+                    "description": "A strange seed was planted on its back at birth. The plant sprouts and grows with this POKéMON.",
+                    "language": "en",
+                    "versions": ["red", "blue", "leafgreen"]
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_the_evolution_chain_as_a_tree() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+        poke_mock
+            .has_evolution_chain(1, RAW_BULBASAUR_EVOLUTION_CHAIN)
+            .await;
+
+        let response = client.get("/pokemon/bulbasaur/evolutions").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let evolutions_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&evolutions_json),
+            json(
+                r#"
+                {
+                    "name": "bulbasaur",
+                    "evolvesFrom": null,
+                    "chain": {
+                        "name": "bulbasaur",
+                        "isBaby": false,
+                        "triggers": [],
+                        "evolvesTo": [
+                            {
+                                "name": "ivysaur",
+                                "isBaby": false,
+                                "triggers": [{ "trigger": "level-up", "minLevel": 16 }],
+                                "evolvesTo": [
+                                    {
+                                        "name": "venusaur",
+                                        "isBaby": false,
+                                        "triggers": [{ "trigger": "level-up", "minLevel": 32 }],
+                                        "evolvesTo": []
+                                    }
+                                ]
+                            }
+                        ]
+                    }
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn evolutions_of_unknown_pokemon_are_not_found() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.no_pokemon_exist().await;
+
+        let response = client.get("/pokemon/mewthree/evolutions").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+
+        let error = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&error),
+            json(r#"{"message": "Unable to find 'mewthree'"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn names_are_case_insensitive() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
+
+        let response = client.get("/pokemon/MewTwo").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let mewtwo_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_eq!(json(&mewtwo_json)["name"], "mewtwo");
+    }
+
+    #[tokio::test]
+    async fn pokemon_can_be_found_by_their_national_pokedex_number() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("150", RAW_MEWTWO).await;
+
+        let response = client.get("/pokemon/%23150").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let mewtwo_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        let mewtwo = json(&mewtwo_json);
+        assert_eq!(mewtwo["name"], "mewtwo");
+        assert_eq!(mewtwo["id"], 150);
+    }
+
+    #[tokio::test]
+    async fn pokemon_can_be_found_by_their_number_in_a_regional_pokedex() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.has_pokedex("kanto", RAW_KANTO_POKEDEX).await;
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+
+        let response = client.get("/pokemon/kanto/50").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let diglett_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        let diglett = json(&diglett_json);
+        assert_eq!(diglett["name"], "diglett");
+        assert_eq!(diglett["id"], 50);
+    }
+
+    #[tokio::test]
+    async fn reports_how_often_the_cache_was_used() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
+        poke_mock
+            .has_battle_data("mewtwo", RAW_MEWTWO_POKEMON)
+            .await;
+
+        for _ in 0..3 {
+            let response = client.get("/pokemon/mewtwo").dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.get("/cache/stats").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let stats = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&stats),
+            json(
+                r#"
+                {
+                    "species": { "hits": 2, "misses": 1, "entries": 1 },
+                    "battle": { "hits": 2, "misses": 1, "entries": 1 }
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn lets_users_know_when_pokemon_were_not_found() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.no_pokemon_exist().await;
+
+        let response = client.get("/pokemon/mewtwo").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let error = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&error),
+            json(
+                r#"
+                {
+                    "message": "Unable to find 'mewtwo'"
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn suggests_similar_names_when_pokemon_were_not_found() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.has_species_index(RAW_SPECIES_INDEX).await;
+        poke_mock.no_pokemon_exist().await;
+
+        let response = client.get("/pokemon/pikchu").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let error = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&error),
+            json(
+                r#"
+                {
+                    "message": "Unable to find 'pikchu'",
+                    "suggestions": ["pikachu", "raichu"]
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn names_the_languages_it_tried_when_there_is_no_description() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("ditto", RAW_DITTO_IN_JAPANESE).await;
+
+        let response = client.get("/pokemon/ditto?lang=de").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let error = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&error),
+            json(
+                r#"
+                {
+                    "message": "'ditto' has no description in any of: de, en"
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn other_errors_result_in_a_500_error() {
+        let (client, poke_mock, _) = setup().await;
+
+        poke_mock.is_present("mewtwo", "{not_even_json}").await;
+
+        let response = client.get("/pokemon/mewtwo").dispatch().await;
+        assert_eq!(response.status(), Status::InternalServerError);
+
+        let error = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&error),
+            json(r#"{"message": "Internal server error"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn when_asking_for_a_cave_pokemon_the_translation_is_in_yoda_speak() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        let response = client.get("/pokemon/translated/diglett").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let diglett_json = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&diglett_json),
+            json(
+                r#"
+                {
+                    "id": 50,
+                    "name": "diglett",
+                    "description": "On plant roots,  lives about one yard underground where it feeds.Above ground,  it sometimes appears.",
+                    "language": "en",
+                    "version": "red",
+                    "habitat":"cave",
+                    "isLegendary":false,
+                    "translation": {
+                        "language": "yoda",
+                        "status": "translated",
+                        "original": "Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground."
+                    }
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn when_asking_for_a_legendary_pokemon_the_translation_is_in_yoda_speak() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        // MewTwo is definitly legendary!
         poke_mock.is_present("mewtwo", RAW_MEWTWO).await;
         translation_mock
             .can_translate(Language::Yoda, MEWTWO_AS_YODA)
@@ -324,10 +1537,18 @@ mod test {
             json(
                 r#"
                 {
+                    "id": 150,
                     "name": "mewtwo",
                     "description": "Created by a scientist after years of horrific gene splicing and dna engineering experiments,  it was.",
+                    "language": "en",
+                    "version": "red",
                     "habitat":"rare",
-                    "isLegendary": true
+                    "isLegendary": true,
+                    "translation": {
+                        "language": "yoda",
+                        "status": "translated",
+                        "original": "It was created by a scientist after years of horrific gene splicing and DNA engineering experiments."
+                    }
                 }
                 "#
             )
@@ -356,10 +1577,120 @@ mod test {
             json(
                 r#"
                 {
+                    "id": 1,
                     "name": "bulbasaur",
                     "description": "A strange seed wast planted on its back at birth. The plant sprouts and grows with this pokémon.",
+                    "language": "en",
+                    "version": "red",
                     "habitat":"grassland",
-                    "isLegendary": false
+                    "isLegendary": false,
+                    "translation": {
+                        "language": "shakespeare",
+                        "status": "translated",
+                        "original": "A strange seed was planted on its back at birth. The plant sprouts and grows with this POKéMON."
+                    }
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn the_style_query_parameter_overrides_the_automatic_translation() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+        translation_mock
+            .can_translate(
+                Language::Pirate,
+                r#"
+                {
+                    "success": { "total": 1 },
+                    "contents": {
+                        "translated": "A strange seed were planted on its back at birth. The plant sprouts and grows with this pokémon.",
+                        "text": "A strange seed was planted on its back at birth. The plant sprouts and grows with this pokémon.",
+                        "translation": "pirate"
+                    }
+                }
+                "#,
+            )
+            .await;
+
+        let response = client
+            .get("/pokemon/translated/bulbasaur?style=pirate")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let bulbasaur = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        assert_eq!(
+            bulbasaur["description"],
+            "A strange seed were planted on its back at birth. The plant sprouts and grows with this pokémon."
+        );
+    }
+
+    #[tokio::test]
+    async fn explains_which_rule_picked_the_translation() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        let response = client
+            .get("/pokemon/translated/diglett?explain=true")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let diglett = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        assert_json_eq!(
+            diglett["explanation"].clone(),
+            json(
+                r#"
+                {
+                    "style": "yoda",
+                    "reason": "rule",
+                    "rule": 1,
+                    "when": { "habitat": "cave" }
+                }
+                "#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_the_translation_rules() {
+        let (client, _, _) = setup().await;
+
+        let response = client.get("/translation/rules").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let rules = response
+            .into_string()
+            .await
+            .expect("Unexpected empty response");
+
+        assert_json_eq!(
+            json(&rules),
+            json(
+                r#"
+                {
+                    "rules": [
+                        { "when": { "legendary": true }, "style": "yoda" },
+                        { "when": { "habitat": "cave" }, "style": "yoda" }
+                    ],
+                    "fallback": "shakespeare"
                 }
                 "#
             )
@@ -367,7 +1698,55 @@ mod test {
     }
 
     #[tokio::test]
-    async fn when_the_translation_fails_it_falls_back_to_the_standard_description() {
+    async fn natural_languages_are_translated_by_libre_translate() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+        translation_mock
+            .can_translate_naturally("de", BULBASAUR_IN_GERMAN)
+            .await;
+
+        let response = client
+            .get("/pokemon/translated/bulbasaur?style=german")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let bulbasaur = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        assert_eq!(
+            bulbasaur["description"],
+            "Bei der Geburt wurde ihm ein seltsamer Samen auf den Rücken gepflanzt."
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_styles_are_a_bad_request() {
+        let (client, _, _) = setup().await;
+
+        let response = client
+            .get("/pokemon/translated/bulbasaur?style=elvish")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+        let error = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        let message = error["message"].as_str().expect("message should be text");
+        assert!(message.starts_with("'elvish' is not a known translation style"));
+        assert!(message.contains("yoda, shakespeare, pirate, minion"));
+    }
+
+    #[tokio::test]
+    async fn when_the_translation_fails_it_falls_back_to_a_local_translation() {
         let (client, poke_mock, translation_mock) = setup().await;
 
         poke_mock.is_present("diglett", RAW_DIGLETT).await;
@@ -385,16 +1764,290 @@ mod test {
             json(
                 r#"
                 {
+                    "id": 50,
                     "name": "diglett",
-                    "description": "Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground.",
+                    "description": "On plant roots, lives about one yard underground where it feeds. Above ground, it sometimes appears.",
+                    "language": "en",
+                    "version": "red",
                     "habitat":"cave",
-                    "isLegendary":false
+                    "isLegendary":false,
+                    "translation": {
+                        "language": "yoda",
+                        "status": "fallback",
+                        "fallbackReason": "upstream_error",
+                        "translatedLocally": true,
+                        "original": "Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground."
+                    }
                 }
                 "#
             )
         );
     }
 
+    #[tokio::test]
+    async fn translations_that_were_bought_before_are_marked_as_cached() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        for status in ["translated", "cached"] {
+            let response = client.get("/pokemon/translated/diglett").dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+            let diglett = json(
+                &response
+                    .into_string()
+                    .await
+                    .expect("Unexpected empty response"),
+            );
+
+            assert_eq!(diglett["translation"]["status"], status);
+        }
+    }
+
+    #[tokio::test]
+    async fn with_a_queue_uncached_translations_happen_in_the_background() {
+        let (client, poke_mock, translation_mock) = setup_with_queue(Some(QueueSettings {
+            interval: Duration::from_millis(10),
+            file: None,
+        }))
+        .await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        let response = client.get("/pokemon/translated/diglett").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("Retry-After"), Some("1"));
+        let diglett = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+        assert_eq!(diglett["translation"]["status"], "queued");
+        assert_eq!(diglett["translation"]["translatedLocally"], true);
+
+        let mut status = diglett["translation"]["status"].clone();
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let response = client.get("/pokemon/translated/diglett").dispatch().await;
+            let diglett = json(
+                &response
+                    .into_string()
+                    .await
+                    .expect("Unexpected empty response"),
+            );
+            status = diglett["translation"]["status"].clone();
+            if status == "cached" {
+                assert_eq!(
+                    diglett["description"],
+                    "On plant roots,  lives about one yard underground where it feeds.Above ground,  it sometimes appears."
+                );
+                break;
+            }
+        }
+        assert_eq!(status, "cached");
+    }
+
+    #[tokio::test]
+    async fn with_a_queue_natural_languages_are_still_translated_right_away() {
+        let (client, poke_mock, translation_mock) = setup_with_queue(Some(QueueSettings {
+            interval: Duration::from_secs(60),
+            file: None,
+        }))
+        .await;
+
+        poke_mock.is_present("bulbasaur", RAW_BULBASAUR).await;
+        translation_mock
+            .can_translate_naturally("de", BULBASAUR_IN_GERMAN)
+            .await;
+
+        let response = client
+            .get("/pokemon/translated/bulbasaur?style=german")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("Retry-After"), None);
+        let bulbasaur = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        assert_eq!(bulbasaur["translation"]["status"], "translated");
+        assert_eq!(
+            bulbasaur["description"],
+            "Bei der Geburt wurde ihm ein seltsamer Samen auf den Rücken gepflanzt."
+        );
+    }
+
+    #[tokio::test]
+    async fn tells_when_the_rate_limit_was_hit() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock.has_hit_rate_limit().await;
+
+        let response = client.get("/pokemon/translated/diglett").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let diglett = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        assert_eq!(diglett["translation"]["status"], "fallback");
+        assert_eq!(diglett["translation"]["fallbackReason"], "rate_limited");
+    }
+
+    #[tokio::test]
+    async fn styles_without_a_local_translation_fall_back_to_the_standard_description() {
+        let (client, poke_mock, translation_mock) = setup().await;
+
+        poke_mock.is_present("diglett", RAW_DIGLETT).await;
+        translation_mock.fails_to_translate(Language::Pirate).await;
+
+        let response = client
+            .get("/pokemon/translated/diglett?style=pirate")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let diglett = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+
+        assert_eq!(
+            diglett["description"],
+            "Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground."
+        );
+        assert_eq!(
+            diglett["translation"]["translatedLocally"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[tokio::test]
+    async fn translates_arbitrary_text() {
+        let (client, _, translation_mock) = setup().await;
+
+        translation_mock
+            .can_translate(Language::Yoda, DIGLETT_AS_YODA)
+            .await;
+
+        for status in ["translated", "cached"] {
+            let response = client
+                .post("/translate/yoda")
+                .body(r#"{"text": "Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground."}"#)
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+
+            assert_json_eq!(
+                json(
+                    &response
+                        .into_string()
+                        .await
+                        .expect("Unexpected empty response")
+                ),
+                json(&format!(
+                    r#"
+                    {{
+                        "text": "On plant roots,  lives about one yard underground where it feeds.Above ground,  it sometimes appears.",
+                        "style": "yoda",
+                        "status": "{}",
+                        "original": "Lives about one yard underground where it feeds on plant roots. It sometimes appears above ground."
+                    }}
+                    "#,
+                    status
+                ))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn texts_to_translate_must_not_be_too_long() {
+        let (client, _, _) = setup().await;
+
+        let text = "Yo ho ho. ".repeat(MAX_TEXT_LENGTH);
+        let response = client
+            .post("/translate/pirate")
+            .body(serde_json::json!({ "text": text }).to_string())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+        assert_json_eq!(
+            json(
+                &response
+                    .into_string()
+                    .await
+                    .expect("Unexpected empty response")
+            ),
+            json(r#"{ "message": "Only texts of up to 1000 characters can be translated" }"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn texts_to_translate_must_be_json() {
+        let (client, _, _) = setup().await;
+
+        let response = client
+            .post("/translate/pirate")
+            .body("Yo ho ho")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[tokio::test]
+    async fn a_used_up_quota_is_a_429_error() {
+        let (client, _, translation_mock) = setup().await;
+
+        translation_mock.has_hit_rate_limit().await;
+
+        let response = client
+            .post("/translate/yoda")
+            .body(r#"{"text": "This is fantastic"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::TooManyRequests);
+        assert_eq!(response.headers().get_one("Retry-After"), Some("1061"));
+
+        // FunTranslations said how long to wait, so it isn't asked again
+        let response = client
+            .post("/translate/yoda")
+            .body(r#"{"text": "This is fantastic"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::TooManyRequests);
+        let retry_after = response
+            .headers()
+            .get_one("Retry-After")
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .expect("Missing Retry-After header");
+        assert!(retry_after > 17 * 60 && retry_after <= 17 * 60 + 41);
+        let body = json(
+            &response
+                .into_string()
+                .await
+                .expect("Unexpected empty response"),
+        );
+        assert!(body["message"]
+            .as_str()
+            .is_some_and(|message| message.starts_with("The translation quota is used up")));
+    }
+
     #[tokio::test]
     async fn when_translated_pokemon_does_not_exist_a_404_is_returned() {
         let (client, poke_mock, _) = setup().await;
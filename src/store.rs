@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StoredTranslation {
+    language: String,
+    text: String,
+    translated: String,
+}
+
+/// Translations we already paid for, keyed by the language and the text that was translated.
+/// Every new translation is appended as a line of JSON to the file, so they survive restarts.
+#[derive(Debug)]
+pub(crate) struct TranslationStore {
+    translations: RwLock<HashMap<(String, String), String>>,
+    file: Option<Arc<Mutex<File>>>,
+}
+
+impl TranslationStore {
+    /// Only remembers translations for as long as the server runs
+    pub(crate) fn in_memory() -> Self {
+        TranslationStore {
+            translations: RwLock::new(HashMap::new()),
+            file: None,
+        }
+    }
+
+    /// Loads all translations from `path`, creating the file if it does not exist yet.
+    /// Lines that can't be read are skipped, so a half-written line doesn't lose the rest.
+    pub(crate) fn open(path: &Path) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        // Start on a fresh line so new translations don't get glued to a half-written one
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            writeln!(file)?;
+        }
+
+        let mut translations = HashMap::new();
+        for (number, line) in contents.lines().enumerate() {
+            match serde_json::from_str::<StoredTranslation>(line) {
+                Ok(stored) => {
+                    translations.insert((stored.language, stored.text), stored.translated);
+                }
+                Err(error) => log::warn!(
+                    "Skipping line {} of {}: {}",
+                    number + 1,
+                    path.display(),
+                    error
+                ),
+            }
+        }
+        log::info!(
+            "Loaded {} translations from {}",
+            translations.len(),
+            path.display()
+        );
+
+        Ok(TranslationStore {
+            translations: RwLock::new(translations),
+            file: Some(Arc::new(Mutex::new(file))),
+        })
+    }
+
+    pub(crate) fn get(&self, language: &str, text: &str) -> Option<String> {
+        self.translations
+            .read()
+            .expect("lock for translations was poisoned")
+            .get(&(language.to_string(), text.to_string()))
+            .cloned()
+    }
+
+    /// Failing to write to the file is only logged, the translation is still remembered in memory.
+    /// The file is written on a blocking thread, so other requests don't wait for the disk.
+    pub(crate) async fn insert(&self, language: &str, text: &str, translated: &str) {
+        self.translations
+            .write()
+            .expect("lock for translations was poisoned")
+            .insert(
+                (language.to_string(), text.to_string()),
+                translated.to_string(),
+            );
+
+        let file = match &self.file {
+            Some(file) => file.clone(),
+            None => return,
+        };
+        let stored = StoredTranslation {
+            language: language.to_string(),
+            text: text.to_string(),
+            translated: translated.to_string(),
+        };
+        let line = serde_json::to_string(&stored).expect("translations are valid JSON");
+
+        let written = tokio::task::spawn_blocking(move || {
+            let mut file = file
+                .lock()
+                .expect("lock for the translation file was poisoned");
+            writeln!(file, "{}", line)
+        })
+        .await;
+        if let Err(error) = written.unwrap_or_else(|error| Err(error.into())) {
+            log::warn!("Unable to store translation: {}", error);
+        }
+    }
+}
+
+impl From<Option<PathBuf>> for TranslationStore {
+    /// Falls back to memory when the file can't be opened, as translating still works without it.
+    fn from(path: Option<PathBuf>) -> Self {
+        match path {
+            Some(path) => TranslationStore::open(&path).unwrap_or_else(|error| {
+                log::error!(
+                    "Unable to open {}, translations won't survive a restart: {}",
+                    path.display(),
+                    error
+                );
+                TranslationStore::in_memory()
+            }),
+            None => TranslationStore::in_memory(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn remembers_translations_across_restarts() {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("translations.jsonl");
+
+        let store = TranslationStore::open(&path).expect("Unable to open the store");
+        store
+            .insert("yoda", "This is fantastic", "Fantastic,  this is")
+            .await;
+        drop(store);
+
+        let store = TranslationStore::open(&path).expect("Unable to reopen the store");
+        assert_eq!(
+            store.get("yoda", "This is fantastic"),
+            Some("Fantastic,  this is".to_string())
+        );
+        assert_eq!(store.get("shakespeare", "This is fantastic"), None);
+    }
+
+    #[tokio::test]
+    async fn skips_lines_it_can_not_read() {
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let path = dir.path().join("translations.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"language":"yoda","text":"This is fantastic","translated":"Fantastic,  this is"}"#,
+                "\n",
+                r#"{"language":"shakes"#,
+            ),
+        )
+        .expect("Unable to write translations");
+
+        let store = TranslationStore::open(&path).expect("Unable to open the store");
+        store
+            .insert("shakespeare", "Any sentence...", "Any sentence, forsooth")
+            .await;
+        drop(store);
+
+        let store = TranslationStore::open(&path).expect("Unable to reopen the store");
+        assert_eq!(
+            store.get("yoda", "This is fantastic"),
+            Some("Fantastic,  this is".to_string())
+        );
+        assert_eq!(
+            store.get("shakespeare", "Any sentence..."),
+            Some("Any sentence, forsooth".to_string())
+        );
+    }
+}
@@ -1,10 +1,22 @@
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
+use crate::libretranslate::{LibreTranslate, LibreTranslateSettings};
+use crate::queue::QueueSettings;
+use crate::rules::{default_rules, Rule};
+use crate::secret::Secret;
+use crate::store::TranslationStore;
+
 #[derive(Debug, Deserialize)]
 struct Contents {
+    #[allow(dead_code)]
     text: String,
     translated: String,
 }
@@ -14,35 +26,329 @@ struct ExtendedTranslation {
     contents: Contents,
 }
 
+/// A service that can translate descriptions into some of the languages in the catalogue.
+#[async_trait]
+pub(crate) trait Translator: std::fmt::Debug + Send + Sync {
+    fn supports(&self, language: Language) -> bool;
+
+    async fn translate(&self, text: &str, language: Language) -> Result<String, Error>;
+}
+
+/// A translated text, and whether we had it already.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Translated {
+    pub(crate) text: String,
+    pub(crate) cached: bool,
+}
+
+/// Hands each translation to the first provider that supports the language,
+/// remembering what they translated.
 #[derive(Debug)]
 pub(crate) struct TranslationClient {
+    providers: Vec<Box<dyn Translator>>,
+    store: Arc<TranslationStore>,
+    cache_file: Option<PathBuf>,
+    /// Which FunTranslations subscription the quota belongs to
+    base_url: String,
+    api_secret: Option<Secret>,
+    /// Shared with FunTranslations, so the quota outlives reloads of the same subscription
+    quota: Arc<Mutex<Quota>>,
+}
+
+/// The fun translations such as Yoda or Shakespeare from https://funtranslations.com
+#[derive(Debug)]
+pub(crate) struct FunTranslations {
     client: Client,
     domain: String,
+    secret: Option<Secret>,
+    quota: Arc<Mutex<Quota>>,
+}
+
+const SECRET_HEADER: &str = "X-Funtranslations-Api-Secret";
+
+/// FunTranslations limits how many translations it allows an hour, 5 on the free tier
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// What FunTranslations told us about how many more translations it allows, and until when.
+#[derive(Debug, Default)]
+struct Quota {
+    /// How many translations the subscription allows per window
+    limit: Option<u32>,
+    remaining: Option<u32>,
+    resets_at: Option<Instant>,
+}
+
+impl Quota {
+    /// How long to wait when FunTranslations doesn't say: the whole window if we don't know the
+    /// limit, otherwise until the next translation of the window should be available again
+    fn default_wait(&self) -> Duration {
+        match self.limit {
+            Some(limit) if limit > 0 => RATE_LIMIT_WINDOW / limit,
+            _ => RATE_LIMIT_WINDOW,
+        }
+    }
+
+    /// How long to wait before asking for another translation, if we know the quota is used up.
+    /// In whole seconds, rounded up so nobody asks too early.
+    fn exhausted_for(&self, now: Instant) -> Option<Duration> {
+        match (self.remaining, self.resets_at) {
+            (Some(0), Some(resets_at)) if resets_at > now => {
+                let wait = resets_at - now;
+                Some(Duration::from_secs(
+                    wait.as_secs() + u64::from(wait.subsec_nanos() > 0),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Takes one translation from the quota before asking for it, so requests running at the
+    /// same time can't each spend the last one. Fails with how long to wait when it's used up.
+    fn reserve(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(wait) = self.exhausted_for(now) {
+            return Err(wait);
+        }
+        self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(1));
+        Ok(())
+    }
+
+    fn update(&mut self, headers: &HeaderMap, now: Instant) {
+        if let Some(limit) = header::<u32>(headers, "X-RateLimit-Limit") {
+            self.limit = Some(limit);
+        }
+        if let Some(remaining) = header::<u32>(headers, "X-RateLimit-Remaining") {
+            self.remaining = Some(remaining);
+        }
+        if let Some(reset) = header::<u64>(headers, "X-RateLimit-Reset") {
+            self.resets_at = Some(now + reset_in(reset));
+        }
+        if let (Some(remaining), Some(limit)) = (self.remaining, self.limit) {
+            log::info!("{} of {} translations left", remaining, limit);
+        }
+    }
+
+    /// Remembers the quota is used up, until the given wait, the reset FunTranslations already
+    /// announced or the default wait for the limit is over
+    fn exhaust(&mut self, wait: Option<Duration>, now: Instant) {
+        let resets_at = match (wait, self.resets_at) {
+            (Some(wait), _) => now + wait,
+            (None, Some(resets_at)) if resets_at > now => resets_at,
+            (None, _) => now + self.default_wait(),
+        };
+        self.remaining = Some(0);
+        self.resets_at = Some(resets_at);
+    }
+}
+
+/// Reads a numeric header, skipping values that are missing, malformed or don't fit
+fn header<T: FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Resets are either given as a unix timestamp or as the number of seconds left
+fn reset_in(reset: u64) -> Duration {
+    const EARLIEST_TIMESTAMP: u64 = 1_000_000_000;
+
+    if reset < EARLIEST_TIMESTAMP {
+        return Duration::from_secs(reset);
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Duration::from_secs(reset.saturating_sub(now))
+}
+
+/// Reads how long to wait from messages such as
+/// "Rate limit of 5 requests per hour exceeded. Please wait for 17 minutes and 41 seconds."
+fn wait_time(message: &str) -> Option<Duration> {
+    let (_, wait) = message.split_once("Please wait for ")?;
+
+    let mut total = 0;
+    let mut amount = None;
+    for word in wait.split_whitespace() {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        match (amount, word.parse::<u64>()) {
+            (None, Ok(number)) => amount = Some(number),
+            (Some(number), _) => {
+                let unit = match word.trim_end_matches('s') {
+                    "hour" => 60 * 60,
+                    "minute" => 60,
+                    "second" => 1,
+                    _ => return None,
+                };
+                total += number * unit;
+                amount = None;
+            }
+            (None, Err(_)) if word == "and" => {}
+            (None, Err(_)) => break,
+        }
+    }
+
+    if total == 0 {
+        None
+    } else {
+        Some(Duration::from_secs(total))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) struct TranslationSettings {
     pub(crate) base_url: String,
+    #[serde(with = "humantime_serde")]
     pub(crate) timeout: Duration,
+    /// Where to keep translations so they are never bought twice, only kept in memory if absent
+    #[serde(default)]
+    pub(crate) cache_file: Option<PathBuf>,
+    /// Which translation to use for which Pokemon, where the first rule to match wins
+    #[serde(default = "default_rules")]
+    pub(crate) rules: Vec<Rule>,
+    /// Translates descriptions to natural languages such as German, if set
+    #[serde(default)]
+    pub(crate) libre_translate: Option<LibreTranslateSettings>,
+    /// Translates in the background instead of waiting for the translation, if set
+    #[serde(default)]
+    pub(crate) queue: Option<QueueSettings>,
+    /// The secret of a paid FunTranslations subscription
+    #[serde(default)]
+    pub(crate) api_secret: Option<Secret>,
+    /// A file holding the secret, to keep it out of the configuration
+    #[serde(default)]
+    pub(crate) api_secret_file: Option<PathBuf>,
+}
+
+impl TranslationSettings {
+    /// Replaces `api_secret` with the one in `api_secret_file`, if set. Done while loading the
+    /// configuration, so a file that can't be read is reported like any other problem.
+    pub(crate) fn read_secret_file(&mut self) -> Result<(), String> {
+        if let Some(path) = &self.api_secret_file {
+            let secret = Secret::from_file(path).map_err(|error| {
+                format!(
+                    "translation_api.api_secret_file: unable to read '{}': {}",
+                    path.display(),
+                    error
+                )
+            })?;
+            self.api_secret = Some(secret);
+        }
+        Ok(())
+    }
 }
 
 impl From<TranslationSettings> for TranslationClient {
     fn from(settings: TranslationSettings) -> Self {
-        TranslationClient::new(settings.base_url, settings.timeout)
+        let store = Arc::new(settings.cache_file.clone().into());
+        TranslationClient::new(settings, Arc::default(), store)
     }
 }
 
-pub(crate) enum Language {
-    Yoda,
-    Shakespear,
+/// Whether a language is one of the fun ones, or a real one with its ISO 639-1 code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Fun,
+    Natural(&'static str),
+}
+
+/// Generates `Language` from a list of variants, the name users ask for them by and their kind,
+/// so adding a style is a single line in the catalogue below.
+macro_rules! catalogue {
+    ($($variant:ident => $name:literal, $kind:expr);* $(;)?) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub(crate) enum Language {
+            $($variant),*
+        }
+
+        impl Language {
+            pub(crate) const ALL: &'static [Language] = &[$(Language::$variant),*];
+
+            pub(crate) fn name(&self) -> &'static str {
+                match self {
+                    $(Language::$variant => $name),*
+                }
+            }
+
+            pub(crate) fn kind(&self) -> Kind {
+                match self {
+                    $(Language::$variant => $kind),*
+                }
+            }
+        }
+    };
+}
+
+catalogue! {
+    Yoda => "yoda", Kind::Fun;
+    Shakespear => "shakespeare", Kind::Fun;
+    Pirate => "pirate", Kind::Fun;
+    Minion => "minion", Kind::Fun;
+    Klingon => "klingon", Kind::Fun;
+    Sith => "sith", Kind::Fun;
+    Morse => "morse", Kind::Fun;
+    Valyrian => "valyrian", Kind::Fun;
+    Dothraki => "dothraki", Kind::Fun;
+    Mandalorian => "mandalorian", Kind::Fun;
+    Huttese => "huttese", Kind::Fun;
+    Gungan => "gungan", Kind::Fun;
+    Cheunh => "cheunh", Kind::Fun;
+    Vulcan => "vulcan", Kind::Fun;
+    Romulan => "romulan", Kind::Fun;
+    OldEnglish => "oldenglish", Kind::Fun;
+    PigLatin => "pig-latin", Kind::Fun;
+    Leetspeak => "leetspeak", Kind::Fun;
+    Doge => "doge", Kind::Fun;
+    Groot => "groot", Kind::Fun;
+    Ermahgerd => "ermahgerd", Kind::Fun;
+    Cockney => "cockney", Kind::Fun;
+    German => "german", Kind::Natural("de");
+    French => "french", Kind::Natural("fr");
+    Spanish => "spanish", Kind::Natural("es");
+    Italian => "italian", Kind::Natural("it");
+    Portuguese => "portuguese", Kind::Natural("pt");
+    Dutch => "dutch", Kind::Natural("nl");
+    Russian => "russian", Kind::Natural("ru");
+    Japanese => "japanese", Kind::Natural("ja");
+    Chinese => "chinese", Kind::Natural("zh");
+    Korean => "korean", Kind::Natural("ko");
 }
 
 impl std::fmt::Display for Language {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Language::Yoda => write!(f, "yoda"),
-            Language::Shakespear => write!(f, "shakespeare"),
-        }
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Serialize for Language {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Language::ALL
+            .iter()
+            .find(|language| language.name().eq_ignore_ascii_case(input))
+            .copied()
+            .ok_or_else(|| {
+                let valid = Language::ALL.iter().map(Language::name).collect::<Vec<_>>();
+                format!(
+                    "'{}' is not a known translation style, the valid styles are: {}",
+                    input,
+                    valid.join(", ")
+                )
+            })
     }
 }
 
@@ -50,8 +356,12 @@ impl std::fmt::Display for Language {
 pub(crate) enum Error {
     #[error("Hit the hourly rate limit when trying to translate")]
     RateLimitHit,
+    #[error("The translation quota is used up for another {}", humantime::format_duration(*.0))]
+    QuotaExhausted(Duration),
     #[error("Tried to deserialize invalid translation")]
     BadJson,
+    #[error("No translation provider is configured for '{0}'")]
+    Unsupported(Language),
     #[error("Unexpected error from translation API")]
     Other(reqwest::Error),
 }
@@ -69,32 +379,151 @@ impl From<reqwest::Error> for Error {
 }
 
 impl TranslationClient {
-    fn new(domain: String, timeout: Duration) -> TranslationClient {
-        let client = Client::builder()
-            .timeout(timeout)
-            .build()
-            .expect("failed to construct a viable PokeApi client");
-        TranslationClient { client, domain }
+    fn new(
+        settings: TranslationSettings,
+        quota: Arc<Mutex<Quota>>,
+        store: Arc<TranslationStore>,
+    ) -> TranslationClient {
+        let mut providers: Vec<Box<dyn Translator>> = vec![Box::new(FunTranslations::new(
+            settings.base_url.clone(),
+            settings.timeout,
+            settings.api_secret.clone(),
+            quota.clone(),
+        ))];
+        if let Some(libre_translate) = settings.libre_translate {
+            providers.push(Box::new(LibreTranslate::from(libre_translate)));
+        }
+
+        TranslationClient {
+            providers,
+            store,
+            cache_file: settings.cache_file,
+            base_url: settings.base_url,
+            api_secret: settings.api_secret,
+            quota,
+        }
+    }
+
+    /// A client for `settings` that keeps what FunTranslations said about the quota unless the
+    /// subscription changed, and the stored translations unless the cache file moved
+    pub(crate) fn reconfigured(&self, settings: TranslationSettings) -> TranslationClient {
+        let store = match settings.cache_file == self.cache_file {
+            true => self.store.clone(),
+            false => Arc::new(settings.cache_file.clone().into()),
+        };
+        let same_subscription =
+            settings.base_url == self.base_url && settings.api_secret == self.api_secret;
+        let quota = match same_subscription {
+            true => self.quota.clone(),
+            false => Arc::default(),
+        };
+        TranslationClient::new(settings, quota, store)
+    }
+
+    /// How long until FunTranslations translates again, if its quota is known to be used up
+    pub(crate) fn exhausted_for(&self) -> Option<Duration> {
+        self.quota
+            .lock()
+            .expect("lock for the quota was poisoned")
+            .exhausted_for(Instant::now())
+    }
+
+    /// A translation we already have, without asking any provider
+    pub(crate) fn stored(&self, text: &str, language: Language) -> Option<Translated> {
+        self.store
+            .get(language.name(), text)
+            .map(|text| Translated { text, cached: true })
     }
 
     pub(crate) async fn translate<S: AsRef<str>>(
         &self,
         text: S,
         language: Language,
-    ) -> Result<String, Error> {
+    ) -> Result<Translated, Error> {
+        let name = language.name();
+        if let Some(translated) = self.stored(text.as_ref(), language) {
+            log::info!("Reusing a stored {} translation", name);
+            return Ok(translated);
+        }
+
+        let provider = self
+            .providers
+            .iter()
+            .find(|provider| provider.supports(language))
+            .ok_or(Error::Unsupported(language))?;
+        let translated = provider.translate(text.as_ref(), language).await?;
+
+        self.store.insert(name, text.as_ref(), &translated).await;
+        Ok(Translated {
+            text: translated,
+            cached: false,
+        })
+    }
+}
+
+impl FunTranslations {
+    fn new(
+        domain: String,
+        timeout: Duration,
+        secret: Option<Secret>,
+        quota: Arc<Mutex<Quota>>,
+    ) -> FunTranslations {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("failed to construct a viable FunTranslations client");
+        FunTranslations {
+            client,
+            domain,
+            secret,
+            quota,
+        }
+    }
+
+    fn lock_quota(&self) -> std::sync::MutexGuard<'_, Quota> {
+        self.quota.lock().expect("lock for the quota was poisoned")
+    }
+}
+
+#[async_trait]
+impl Translator for FunTranslations {
+    fn supports(&self, language: Language) -> bool {
+        language.kind() == Kind::Fun
+    }
+
+    async fn translate(&self, text: &str, language: Language) -> Result<String, Error> {
         #[derive(Serialize)]
         struct Text<'a> {
             text: &'a str,
         }
 
-        let translation = self
+        if let Err(wait) = self.lock_quota().reserve(Instant::now()) {
+            // Asking anyway would only use up the next hour's quota sooner
+            return Err(Error::QuotaExhausted(wait));
+        }
+
+        let mut request = self
             .client
             .post(format!("{}/translate/{}", self.domain, language))
-            .json(&Text {
-                text: text.as_ref(),
-            })
-            .send()
-            .await?
+            .json(&Text { text });
+        if let Some(secret) = &self.secret {
+            request = request.header(SECRET_HEADER, secret.expose());
+        }
+
+        let response = request.send().await?;
+        self.lock_quota().update(response.headers(), Instant::now());
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let message = response.text().await.unwrap_or_default();
+            let now = Instant::now();
+            let mut quota = self.lock_quota();
+            quota.exhaust(wait_time(&message), now);
+            return Err(quota
+                .exhausted_for(now)
+                .map_or(Error::RateLimitHit, Error::QuotaExhausted));
+        }
+
+        let translation = response
             .error_for_status()?
             .json::<ExtendedTranslation>()
             .await?;
@@ -138,6 +567,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_styles_from_the_catalogue() {
+        assert_eq!("pirate".parse::<Language>(), Ok(Language::Pirate));
+        assert_eq!("Pig-Latin".parse::<Language>(), Ok(Language::PigLatin));
+        assert_eq!(Language::Shakespear.to_string(), "shakespeare".to_string());
+
+        let error = "elvish".parse::<Language>().unwrap_err();
+        assert!(error.starts_with("'elvish' is not a known translation style"));
+        assert!(error.contains("yoda, shakespeare, pirate"));
+    }
+
     #[tokio::test]
     async fn translates_a_simple_sentence_to_yoda_speak() {
         let mock_server = mocks::setup_translation_api().await;
@@ -155,7 +595,7 @@ mod tests {
             .await
             .expect("Unable to get translation");
 
-        assert_eq!(yoda_translation, "Fantastic,  this is".to_string());
+        assert_eq!(yoda_translation.text, "Fantastic,  this is".to_string());
     }
 
     #[tokio::test]
@@ -176,13 +616,41 @@ mod tests {
             .expect("Unable to get translation");
 
         assert_eq!(
-            shakespeare_translation,
+            shakespeare_translation.text,
             "Thee did giveth mr. Tim a hearty meal, but unfortunately what did doth englut did maketh him kicketh the bucket.".to_string()
         );
     }
 
     #[tokio::test]
-    async fn reports_an_error_when_rate_limit_has_been_hit() {
+    async fn translates_the_same_text_only_once() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate(
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+            )
+            .await;
+
+        for cached in [false, true] {
+            let yoda_translation = mock_server
+                .client()
+                .translate("This is fantastic", Language::Yoda)
+                .await
+                .expect("Unable to get translation");
+
+            assert_eq!(
+                yoda_translation,
+                Translated {
+                    text: "Fantastic,  this is".to_string(),
+                    cached,
+                }
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_how_long_to_wait_when_the_rate_limit_has_been_hit() {
         let mock_server = mocks::setup_translation_api().await;
 
         mock_server.has_hit_rate_limit().await;
@@ -193,7 +661,282 @@ mod tests {
             .await
             .expect_err("Request should have failed due to rate limiting");
 
-        assert_matches!(err, Error::RateLimitHit)
+        assert_matches!(err, Error::QuotaExhausted(wait) if wait == Duration::from_secs(17 * 60 + 41))
+    }
+
+    #[tokio::test]
+    async fn does_not_ask_again_until_the_rate_limit_resets() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server.has_hit_rate_limit().await;
+
+        let client = mock_server.client();
+        let _ = client.translate("This is fantastic", Language::Yoda).await;
+        let err = client
+            .translate("Any sentence...", Language::Yoda)
+            .await
+            .expect_err("Request should have been skipped due to rate limiting");
+
+        assert_matches!(err, Error::QuotaExhausted(wait) if wait > Duration::from_secs(17 * 60));
+    }
+
+    #[tokio::test]
+    async fn keeps_track_of_the_quota_from_the_response_headers() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate_with_quota(
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+                0,
+                "3600",
+            )
+            .await;
+
+        let client = mock_server.client();
+        client
+            .translate("This is fantastic", Language::Yoda)
+            .await
+            .expect("Unable to get translation");
+        let err = client
+            .translate("Any sentence...", Language::Yoda)
+            .await
+            .expect_err("Request should have been skipped as the quota is used up");
+
+        assert_matches!(err, Error::QuotaExhausted(_));
+    }
+
+    #[tokio::test]
+    async fn does_not_spend_the_last_translation_of_the_quota_twice() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate_with_quota(
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+                1,
+                "3600",
+            )
+            .await;
+        mock_server
+            .can_translate_with_quota(
+                Language::Shakespear,
+                include_str!("../fixtures/translation/shakespeare.json"),
+                0,
+                "3600",
+            )
+            .await;
+
+        let client = mock_server.client();
+        client
+            .translate("This is fantastic", Language::Yoda)
+            .await
+            .expect("Unable to get translation");
+        let (first, second) = tokio::join!(
+            client.translate("One sentence...", Language::Shakespear),
+            client.translate("Another sentence...", Language::Shakespear),
+        );
+
+        assert_matches!(
+            (first, second),
+            (Ok(_), Err(Error::QuotaExhausted(_))) | (Err(Error::QuotaExhausted(_)), Ok(_))
+        );
+    }
+
+    #[tokio::test]
+    async fn asks_again_when_the_quota_has_been_reset() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate_with_quota(
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+                0,
+                "0",
+            )
+            .await;
+        mock_server
+            .can_translate(
+                Language::Shakespear,
+                include_str!("../fixtures/translation/shakespeare.json"),
+            )
+            .await;
+
+        let client = mock_server.client();
+        client
+            .translate("This is fantastic", Language::Yoda)
+            .await
+            .expect("Unable to get translation");
+        client
+            .translate("Any sentence...", Language::Shakespear)
+            .await
+            .expect("Unable to get translation after the reset");
+    }
+
+    #[tokio::test]
+    async fn sends_the_secret_of_a_paid_subscription() {
+        let mock_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate_with_secret(
+                "very-secret",
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+            )
+            .await;
+
+        let client = TranslationClient::from(TranslationSettings {
+            api_secret: Some(Secret::new("very-secret")),
+            ..mock_server.settings().clone()
+        });
+        let yoda_translation = client
+            .translate("This is fantastic", Language::Yoda)
+            .await
+            .expect("Unable to get translation");
+
+        assert_eq!(yoda_translation.text, "Fantastic,  this is".to_string());
+    }
+
+    #[tokio::test]
+    async fn starts_afresh_with_the_quota_of_another_subscription() {
+        let mock_server = mocks::setup_translation_api().await;
+        let other_server = mocks::setup_translation_api().await;
+
+        mock_server
+            .can_translate_with_secret(
+                "very-secret",
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+            )
+            .await;
+        mock_server.has_hit_rate_limit().await;
+        other_server
+            .can_translate(
+                Language::Yoda,
+                include_str!("../fixtures/translation/yoda.json"),
+            )
+            .await;
+
+        let client = mock_server.client();
+        client
+            .translate("This is fantastic", Language::Yoda)
+            .await
+            .expect_err("The rate limit should have been hit");
+
+        let paid = client.reconfigured(TranslationSettings {
+            api_secret: Some(Secret::new("very-secret")),
+            ..mock_server.settings().clone()
+        });
+        paid.translate("This is fantastic", Language::Yoda)
+            .await
+            .expect("The paid subscription should have its own quota");
+
+        let elsewhere = client.reconfigured(other_server.settings().clone());
+        elsewhere
+            .translate("This is fantastic too", Language::Yoda)
+            .await
+            .expect("Another FunTranslations should have its own quota");
+    }
+
+    #[tokio::test]
+    async fn reads_the_secret_from_a_file() {
+        let mock_server = mocks::setup_translation_api().await;
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+        let secret_file = dir.path().join("funtranslations");
+        std::fs::write(&secret_file, "very-secret\n").expect("Unable to write the secret");
+
+        let mut settings = TranslationSettings {
+            api_secret: Some(Secret::new("overridden")),
+            api_secret_file: Some(secret_file),
+            ..mock_server.settings().clone()
+        };
+        settings
+            .read_secret_file()
+            .expect("Unable to read the secret");
+
+        assert_eq!(settings.api_secret, Some(Secret::new("very-secret")));
+    }
+
+    #[tokio::test]
+    async fn reports_a_secret_file_it_cannot_read() {
+        let mock_server = mocks::setup_translation_api().await;
+        let dir = tempfile::tempdir().expect("Unable to create a temporary directory");
+
+        let mut settings = TranslationSettings {
+            api_secret_file: Some(dir.path().to_path_buf()),
+            ..mock_server.settings().clone()
+        };
+
+        assert_matches!(
+            settings.read_secret_file(),
+            Err(problem) if problem.starts_with("translation_api.api_secret_file: unable to read")
+        );
+    }
+
+    #[test]
+    fn waits_less_the_more_translations_the_subscription_allows() {
+        let now = Instant::now();
+        let mut unknown = Quota::default();
+        let mut free = Quota {
+            limit: Some(5),
+            ..Quota::default()
+        };
+        let mut paid = Quota {
+            limit: Some(3600),
+            ..Quota::default()
+        };
+
+        unknown.exhaust(None, now);
+        free.exhaust(None, now);
+        paid.exhaust(None, now);
+
+        assert_eq!(unknown.exhausted_for(now), Some(RATE_LIMIT_WINDOW));
+        assert_eq!(free.exhausted_for(now), Some(Duration::from_secs(12 * 60)));
+        assert_eq!(paid.exhausted_for(now), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn keeps_the_announced_reset_when_not_told_how_long_to_wait() {
+        let now = Instant::now();
+        let mut quota = Quota {
+            limit: Some(5),
+            resets_at: Some(now + Duration::from_secs(40 * 60)),
+            ..Quota::default()
+        };
+
+        quota.exhaust(None, now);
+
+        assert_eq!(quota.exhausted_for(now), Some(Duration::from_secs(40 * 60)));
+    }
+
+    #[test]
+    fn skips_quota_headers_that_do_not_fit() {
+        let now = Instant::now();
+        let mut quota = Quota {
+            limit: Some(5),
+            remaining: Some(3),
+            ..Quota::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("X-RateLimit-Limit", "4294967296".parse().unwrap());
+        headers.insert("X-RateLimit-Remaining", "-1".parse().unwrap());
+
+        quota.update(&headers, now);
+
+        assert_eq!(quota.limit, Some(5));
+        assert_eq!(quota.remaining, Some(3));
+    }
+
+    #[test]
+    fn reads_the_wait_time_from_the_rate_limit_message() {
+        let message = "Too Many Requests: Rate limit of 5 requests per hour exceeded. Please wait for 17 minutes and 41 seconds.";
+        assert_eq!(wait_time(message), Some(Duration::from_secs(17 * 60 + 41)));
+
+        assert_eq!(
+            wait_time("Please wait for 1 hour."),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(wait_time("Too Many Requests"), None);
     }
 
     #[tokio::test]